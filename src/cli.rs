@@ -1,16 +1,363 @@
+use crate::web::{Condition, ConditionFilter, DiscogsScraper, Grading, Price, PriceStats};
 use clap::{Parser, Subcommand};
-use inquire::{validator::Validation, CustomType, Select};
-use owo_colors::OwoColorize;
+use inquire::{validator::Validation, Confirm, MultiSelect, Select, Text};
+use itertools::Itertools;
+use owo_colors::{OwoColorize, Stream};
+use regex::Regex;
+use std::cmp::Ordering;
+use std::io::IsTerminal;
+use std::sync::OnceLock;
 use tabled::builder::Builder;
 use tabled::settings::*;
 use terminal_size::{terminal_size, Width as TermWidth};
 
+const SELLERS_HEADER: &[&str] = &[
+    "Seller",
+    "Rating",
+    "Amount",
+    "Shipping From",
+    "Condition",
+    "Price",
+    "Converted",
+    "Deal",
+];
+const UNCONVERTED_SELLERS_HEADER: &[&str] = &[
+    "Seller",
+    "Rating",
+    "Amount",
+    "Shipping From",
+    "Condition",
+    "Price",
+    "Deal",
+];
+const STATS_HEADER: &[&str] = &["Lowest", "For Sale", "Low", "Median", "High", "Last Sold"];
+const SUGGESTIONS_HEADER: &[&str] = &["M", "NM", "VG+", "VG", "G+", "G", "F", "P"];
+const ITEMS_HEADER: &[&str] = &["Realease", "Condition", "Price", "Converted"];
+const UNCONVERTED_ITEMS_HEADER: &[&str] = &["Realease", "Condition", "Price"];
+const RELEASE_HEADER: &[&str] = &["Release", "Status", "Info", "Details"];
+
+/// Header of the sellers of a release, with a "Converted" column only when prices are converted.
+pub fn sellers_header(converted: bool) -> &'static [&'static str] {
+    match converted {
+        true => SELLERS_HEADER,
+        false => UNCONVERTED_SELLERS_HEADER,
+    }
+}
+
+/// Header of the wanted items of a seller, with a "Converted" column only when prices are
+/// converted.
+pub fn items_header(converted: bool) -> &'static [&'static str] {
+    match converted {
+        true => ITEMS_HEADER,
+        false => UNCONVERTED_ITEMS_HEADER,
+    }
+}
+
 #[derive(Debug, Subcommand)]
 pub enum Commands {
-    Add { release: String },
-    Remove { release: String },
-    Cart,
-    Wantlist { query: Option<String> },
+    Add {
+        release: String,
+    },
+    Remove {
+        release: String,
+    },
+    Cart {
+        #[command(subcommand)]
+        action: Option<CartCommands>,
+    },
+    Wantlist {
+        query: Option<String>,
+        /// Look up this release id in the wantlist, followed by the items with the same title
+        #[arg(long, conflicts_with = "query")]
+        release: Option<u64>,
+    },
+    /// Pick a random wantlist item
+    Random {
+        /// Only pick items that are for sale
+        #[arg(long)]
+        with_sellers: bool,
+        /// Only pick items whose format contains this text, e.g. LP
+        #[arg(long)]
+        format: Option<String>,
+        /// Only pick items of this genre or style
+        #[arg(long)]
+        genre: Option<String>,
+        /// Only pick items whose lowest listing is at or below this price, e.g. 20EUR
+        #[arg(long, value_parser = parse_price)]
+        under: Option<Price>,
+        /// Show the sellers of the picked item right away
+        #[arg(long)]
+        sellers: bool,
+    },
+    /// Browse the wantlist, sellers, their items and the cart in a full-screen interface
+    Tui {
+        /// Only show wantlist items matching this search
+        query: Option<String>,
+    },
+    /// Search the Discogs database for releases, masters, artists or labels
+    Search {
+        query: Option<String>,
+        /// Kind of result to look for
+        #[arg(long = "type", value_enum, default_value_t = SearchType::Release)]
+        kind: SearchType,
+        #[arg(long)]
+        genre: Option<String>,
+        #[arg(long)]
+        style: Option<String>,
+        /// Format, e.g. Vinyl, CD, Cassette
+        #[arg(long)]
+        format: Option<String>,
+        #[arg(long)]
+        country: Option<String>,
+        /// Year of release
+        #[arg(long)]
+        year: Option<String>,
+        #[arg(long)]
+        label: Option<String>,
+        /// Number of pages of 100 results to read
+        #[arg(long, default_value_t = 1)]
+        pages: usize,
+    },
+    /// Find releases by barcode or catalog number, with their wantlist and collection status and
+    /// lowest marketplace price
+    Lookup {
+        /// Barcode or catalog number; a code of 8 or more digits is read as a barcode first
+        code: String,
+        /// Only search barcodes
+        #[arg(long, conflicts_with = "catno")]
+        barcode: bool,
+        /// Only search catalog numbers
+        #[arg(long)]
+        catno: bool,
+    },
+    /// Add masters and releases of an artist's or a label's discography to the wantlist
+    Discography {
+        #[arg(value_enum)]
+        kind: DiscographyKind,
+        /// Name of the artist or label to search, or its id with --id
+        name: String,
+        /// Take NAME as the id of the artist or label
+        #[arg(long)]
+        id: bool,
+        /// Only keep releases whose format contains this text, e.g. LP; masters add their
+        /// versions in that format
+        #[arg(long)]
+        format: Option<String>,
+        /// Only keep items released in this year or later
+        #[arg(long)]
+        from: Option<u32>,
+        /// Only keep items released in this year or earlier
+        #[arg(long)]
+        to: Option<u32>,
+        /// Only keep the artist's items with this role, e.g. Main, Appearance (repeatable)
+        #[arg(long, default_value = "Main")]
+        role: Vec<String>,
+    },
+    /// Browse the versions of a master release, given by id, link or search
+    Versions {
+        /// Album name to search, a master link, or the master id with --id
+        master: String,
+        /// Take MASTER as the id of the master release
+        #[arg(long)]
+        id: bool,
+    },
+    /// Show the tracklist, labels, identifiers, credits, community statistics and images of a
+    /// release
+    Release {
+        release_id: u64,
+    },
+    /// List your purchases, or show and message a single order
+    Orders {
+        #[command(subcommand)]
+        action: Option<OrderCommands>,
+    },
+    /// List, create, reprice and delete the listings you sell
+    Inventory {
+        #[command(subcommand)]
+        action: InventoryCommands,
+    },
+    /// Show or change the notes and rating of a wantlist item
+    Notes {
+        release_id: u64,
+        /// Replace the notes with this text
+        #[arg(long)]
+        set: Option<String>,
+        /// Rate the item from 1 to 5 (0 clears the rating)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=5))]
+        rating: Option<u8>,
+    },
+    /// List, search and organize the releases in your collection
+    Collection {
+        #[command(subcommand)]
+        action: CollectionCommands,
+    },
+    /// Show the asking prices recorded for a release in the database (requires --db)
+    History {
+        release_id: u64,
+    },
+    /// Report new listings, new sellers and price drops on wantlist items
+    Watch {
+        /// Only watch wantlist items whose artists and title contain these words
+        query: Option<String>,
+        /// Report new listings at or below this price, e.g. 25EUR (25 is in your Discogs currency)
+        #[arg(long, value_parser = parse_price)]
+        max_price: Option<Price>,
+        /// Maximum price for a single release, as RELEASE_ID=PRICE (repeatable)
+        #[arg(long, value_parser = parse_item_max)]
+        item_max: Vec<(u64, Price)>,
+        /// File storing the listings seen in previous scans
+        #[arg(long, default_value = ".discogs-watch.json")]
+        state: String,
+        /// Scan again every INTERVAL minutes instead of exiting after one scan
+        #[arg(long)]
+        interval: Option<u64>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CartCommands {
+    /// Remove a listing from the cart
+    Remove { listing_id: String },
+    /// Remove every item bought from a seller
+    Empty { seller: String },
+    /// Select the shipping method of a seller's order, by number or name
+    Shipping { seller: String, method: String },
+    /// Replace a listing with another seller's copy of the same release
+    Move { listing_id: String },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum InventoryCommands {
+    /// List your listings, or those whose release or comments contain QUERY
+    List { query: Option<String> },
+    /// Create listings from a CSV file with release_id, condition, price and comments columns
+    /// (and optionally sleeve_condition)
+    Create { file: String },
+    /// Change prices by a percentage (+10%), an amount (-2), to a value (=12.50) or to the
+    /// median sold price (median), for the given listings or all of them
+    Price {
+        #[arg(allow_hyphen_values = true, value_parser = PriceChange::parse)]
+        change: PriceChange,
+        listing_ids: Vec<u64>,
+        /// Only show the new prices
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Delete listings
+    Delete {
+        #[arg(required = true)]
+        listing_ids: Vec<u64>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub enum PriceChange {
+    Percent(f32),
+    Amount(f32),
+    Set(f32),
+    Median,
+}
+
+impl PriceChange {
+    pub fn parse(input: &str) -> Result<PriceChange, String> {
+        let input = input.trim();
+        let number = |s: &str| {
+            s.trim()
+                .parse::<f32>()
+                .map_err(|_| format!("invalid price change '{}'", input))
+        };
+        if input.eq_ignore_ascii_case("median") {
+            Ok(PriceChange::Median)
+        } else if let Some(value) = input.strip_prefix('=') {
+            number(value).map(PriceChange::Set)
+        } else if let Some(percent) = input.strip_suffix('%') {
+            number(percent).map(PriceChange::Percent)
+        } else {
+            number(input).map(PriceChange::Amount)
+        }
+    }
+
+    /// New price, rounded to cents. Matching the median needs the median sold price.
+    pub fn apply(&self, price: f32, median: Option<f32>) -> Option<f32> {
+        let new = match self {
+            PriceChange::Percent(percent) => price * (1.0 + percent / 100.0),
+            PriceChange::Amount(amount) => price + amount,
+            PriceChange::Set(value) => *value,
+            PriceChange::Median => median?,
+        };
+        Some((new * 100.0).round() / 100.0).filter(|new| *new > 0.0)
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum OrderCommands {
+    /// Show the items, total and messages of an order
+    Show { order_id: String },
+    /// Send a message to the seller of an order
+    Message { order_id: String, message: String },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CollectionCommands {
+    /// List the collection folders
+    Folders,
+    /// List the items of the collection, or those matching a search
+    List {
+        /// Only show items whose artist, title or release id contain this text
+        query: Option<String>,
+        /// Only show items of this folder, by id or name
+        #[arg(long)]
+        folder: Option<String>,
+    },
+    /// Add a release to a folder
+    Add {
+        release_id: u64,
+        /// Folder to add the release to, by id or name
+        #[arg(long, default_value = "Uncategorized")]
+        folder: String,
+    },
+    /// Move a release to another folder, by id or name
+    Move { release_id: u64, folder: String },
+    /// Remove a release from the collection
+    Remove { release_id: u64 },
+    /// Rate a release from 1 to 5 (0 clears the rating)
+    Rate {
+        release_id: u64,
+        #[arg(value_parser = clap::value_parser!(u8).range(0..=5))]
+        rating: u8,
+    },
+    /// Add the releases of an order (or given releases) to a folder and remove them from the wantlist
+    Receive {
+        #[arg(required_unless_present = "order")]
+        release_ids: Vec<u64>,
+        /// Order whose releases were received
+        #[arg(long)]
+        order: Option<String>,
+        /// Folder to add the releases to, by id or name
+        #[arg(long, default_value = "Uncategorized")]
+        folder: String,
+    },
+    /// Set a custom field of a release, such as "Media Condition" or "Notes", by id or name
+    Field {
+        release_id: u64,
+        field: String,
+        value: String,
+    },
+}
+
+fn parse_price(s: &str) -> Result<Price, String> {
+    Price::parse(s).ok_or(format!("invalid price '{}'", s))
+}
+
+fn parse_item_max(s: &str) -> Result<(u64, Price), String> {
+    let (id, price) = s
+        .split_once('=')
+        .ok_or(format!("expected RELEASE_ID=PRICE, got '{}'", s))?;
+    let id = id
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid release id '{}'", id))?;
+    Ok((id, parse_price(price)?))
 }
 
 #[derive(Parser, Debug)]
@@ -18,18 +365,293 @@ pub enum Commands {
 pub struct Args {
     pub cookies: String,
 
+    /// Hide listings whose media grade is below this one (M, NM, VG+, VG, G+, G, F, P)
+    #[arg(long, global = true)]
+    pub min_media: Option<Condition>,
+
+    /// Hide listings whose sleeve grade is below this one (M, NM, VG+, VG, G+, G, F, P)
+    #[arg(long, global = true)]
+    pub min_sleeve: Option<Condition>,
+
+    /// Order listings by condition, best first
+    #[arg(long, global = true)]
+    pub sort_condition: bool,
+
+    /// SQLite database recording scraped wantlist items, listings, sellers and carts
+    #[arg(long, global = true)]
+    pub db: Option<String>,
+
+    /// Minutes during which pages stored in the database are reused instead of scraped again
+    #[arg(long, global = true, default_value_t = 15)]
+    pub cache_ttl: i64,
+
+    /// Sort tables by a column, as COLUMN or COLUMN:desc
+    #[arg(long, global = true)]
+    pub sort: Option<String>,
+
+    /// Only show rows containing this text, or matching a regex when prefixed with "re:"
+    #[arg(long, global = true, value_parser = RowFilter::parse)]
+    pub filter: Option<RowFilter>,
+
+    /// Hide a table column (repeatable)
+    #[arg(long, global = true)]
+    pub hide: Vec<String>,
+
+    /// Hide listings above the maximum price written in the wantlist notes (e.g. "max:25EUR")
+    /// instead of marking them
+    #[arg(long, global = true)]
+    pub hide_over_max: bool,
+
+    /// Show prices converted to this currency (e.g. EUR, USD) in an extra column
+    #[arg(long, global = true)]
+    pub currency: Option<String>,
+
+    /// JSON file with the exchange rates used by --currency, fetched when missing
+    #[arg(long, global = true, default_value = ".discogs-rates.json")]
+    pub rates: String,
+
+    /// Fetch up-to-date exchange rates into the --rates file
+    #[arg(long, global = true)]
+    pub refresh_rates: bool,
+
+    /// Wrap tables to this many columns instead of the terminal width
+    #[arg(long, global = true)]
+    pub width: Option<usize>,
+
+    /// When to color output; "auto" disables colors when NO_COLOR is set or output is not a terminal
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// Border style of tables: rounded, plain (ASCII) or markdown
+    #[arg(long, global = true, value_enum, default_value_t = TableStyle::Rounded)]
+    pub table_style: TableStyle,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
+impl Args {
+    pub fn table_view(&self) -> TableView {
+        let sort = self.sort.as_ref().map(|sort| match sort.rsplit_once(':') {
+            Some((column, "desc")) => (column.to_string(), true),
+            Some((column, "asc")) => (column.to_string(), false),
+            _ => (sort.to_string(), false),
+        });
+        TableView {
+            sort,
+            filter: self.filter.clone(),
+            hidden: self.hide.clone(),
+        }
+    }
+
+    /// Applies the width, color and style flags to every table and message printed afterwards.
+    pub fn configure_output(&self) {
+        let color = match self.color {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal()
+            }
+        };
+        owo_colors::set_override(color);
+        RENDER
+            .set(Render {
+                width: self.width,
+                color,
+                style: self.table_style,
+            })
+            .expect("Output already configured.");
+    }
+
+    pub fn condition_filter(&self) -> ConditionFilter {
+        ConditionFilter {
+            min_media: self.min_media,
+            min_sleeve: self.min_sleeve,
+            sort: self.sort_condition,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum SearchType {
+    Release,
+    Master,
+    Artist,
+    Label,
+}
+
+impl SearchType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchType::Release => "release",
+            SearchType::Master => "master",
+            SearchType::Artist => "artist",
+            SearchType::Label => "label",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum DiscographyKind {
+    Artist,
+    Label,
+}
+
+impl DiscographyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiscographyKind::Artist => "artist",
+            DiscographyKind::Label => "label",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum TableStyle {
+    Rounded,
+    Plain,
+    Markdown,
+}
+
+/// Width used when neither --width nor the terminal nor $COLUMNS tell how wide output can be.
+const FALLBACK_WIDTH: usize = 120;
+
+#[derive(Debug)]
+struct Render {
+    width: Option<usize>,
+    color: bool,
+    style: TableStyle,
+}
+
+static RENDER: OnceLock<Render> = OnceLock::new();
+
+fn render() -> &'static Render {
+    RENDER.get_or_init(|| Render {
+        width: None,
+        color: std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal(),
+        style: TableStyle::Rounded,
+    })
+}
+
+/// Width tables are wrapped to. Output that isn't a terminal, as when piping to a file or running
+/// from cron, falls back to $COLUMNS and then to a fixed width.
+fn output_width() -> usize {
+    render()
+        .width
+        .or_else(|| terminal_size().map(|(TermWidth(width), _)| width as usize))
+        .or_else(|| std::env::var("COLUMNS").ok()?.parse().ok())
+        .unwrap_or(FALLBACK_WIDTH)
+}
+
+fn red(s: &str) -> String {
+    s.if_supports_color(Stream::Stdout, |s| s.red()).to_string()
+}
+
 #[derive(Debug)]
 pub enum TableType {
     Default,
     Cart,
+    Info,
+}
+
+pub enum CartMenu {
+    RemoveItem,
+    EmptyOrder,
+    ChangeShipping,
+    MoveItem,
+    Exit,
+}
+
+impl std::fmt::Display for CartMenu {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CartMenu::RemoveItem => write!(f, "Remove Item"),
+            CartMenu::EmptyOrder => write!(f, "Empty Seller Order"),
+            CartMenu::ChangeShipping => write!(f, "Change Shipping"),
+            CartMenu::MoveItem => write!(f, "Move Item To Another Seller"),
+            CartMenu::Exit => write!(f, "Exit"),
+        }
+    }
+}
+
+pub enum VersionMenu {
+    AddToWantlist,
+    Listings,
+    Details,
+    View,
+    Exit,
+}
+
+impl std::fmt::Display for VersionMenu {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            VersionMenu::AddToWantlist => write!(f, "Add To Wantlist"),
+            VersionMenu::Listings => write!(f, "Marketplace Listings"),
+            VersionMenu::Details => write!(f, "Release Details"),
+            VersionMenu::View => write!(f, "Sort / Filter / Columns"),
+            VersionMenu::Exit => write!(f, "Exit"),
+        }
+    }
+}
+
+pub enum WantlistMenu {
+    Select,
+    View,
+    Notes,
+    Details,
+    GoBack,
+    Exit,
+}
+
+impl std::fmt::Display for WantlistMenu {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WantlistMenu::Select => write!(f, "Select"),
+            WantlistMenu::View => write!(f, "Sort / Filter / Columns"),
+            WantlistMenu::Notes => write!(f, "Edit Notes / Rating"),
+            WantlistMenu::Details => write!(f, "Release Details"),
+            WantlistMenu::GoBack => write!(f, "Go Back"),
+            WantlistMenu::Exit => write!(f, "Exit"),
+        }
+    }
+}
+
+pub enum SearchAction {
+    AddToWantlist,
+    AddLps,
+    Listings,
+    Details,
+    Versions,
+    Discography,
+    Link,
+    GoBack,
+}
+
+impl std::fmt::Display for SearchAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SearchAction::AddToWantlist => write!(f, "Add To Wantlist"),
+            SearchAction::AddLps => write!(f, "Add LPs To Wantlist"),
+            SearchAction::Listings => write!(f, "Marketplace Listings"),
+            SearchAction::Details => write!(f, "Release Details"),
+            SearchAction::Versions => write!(f, "Browse Versions"),
+            SearchAction::Discography => write!(f, "Discography To Wantlist"),
+            SearchAction::Link => write!(f, "Show Link"),
+            SearchAction::GoBack => write!(f, "Go Back"),
+        }
+    }
 }
 
 pub enum MenuOptions {
-    SelectId,
+    Select,
+    View,
     GoBack,
     Exit,
 }
@@ -39,80 +661,531 @@ impl std::fmt::Display for MenuOptions {
         match self {
             MenuOptions::GoBack => write!(f, "Go Back"),
             MenuOptions::Exit => write!(f, "Exit"),
-            MenuOptions::SelectId => write!(f, "Select Id"),
+            MenuOptions::Select => write!(f, "Select"),
+            MenuOptions::View => write!(f, "Sort / Filter / Columns"),
         }
     }
 }
 
+enum ColumnKind {
+    Text,
+    Number,
+    Price,
+    Condition,
+}
+
+fn column_kind(header: &str) -> ColumnKind {
+    match header {
+        "Price" | "Converted" | "Lowest" | "Average" | "Highest" | "Low" | "Median" | "High" => {
+            ColumnKind::Price
+        }
+        "Year" | "Amount" | "Sellers" | "Listings" | "For Sale" | "Rating" | "Have" | "Want" => {
+            ColumnKind::Number
+        }
+        "Condition" => ColumnKind::Condition,
+        _ => ColumnKind::Text,
+    }
+}
+
+/// Orders two cells of the column named `header`: prices and numbers by value, conditions by
+/// grade and anything else alphabetically. Cells that can't be read sort last.
+pub fn compare_cells(header: &str, a: &str, b: &str) -> Ordering {
+    fn by_value<T: PartialOrd>(a: Option<T>, b: Option<T>) -> Ordering {
+        match (a, b) {
+            (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+    match column_kind(header) {
+        ColumnKind::Number | ColumnKind::Price => by_value(
+            Price::parse(a).map(|p| p.value),
+            Price::parse(b).map(|p| p.value),
+        ),
+        ColumnKind::Condition => by_value(a.parse::<Grading>().ok(), b.parse::<Grading>().ok()),
+        ColumnKind::Text => a.to_lowercase().cmp(&b.to_lowercase()),
+    }
+}
+
 pub fn print_table(
     header: &'static [&str],
-    table: &Vec<Vec<String>>,
+    table: &[Vec<String>],
+    title: &str,
+    table_type: TableType,
+) {
+    print_view(header, table, title, table_type, &TableView::default());
+}
+
+/// Prints the rows of `table` selected and ordered by `view`. The index column keeps the
+/// position of each row in `table`, so it can still be used to select it.
+pub fn print_view(
+    header: &[&str],
+    table: &[Vec<String>],
     title: &str,
     table_type: TableType,
+    view: &TableView,
 ) {
     let mut builder = Builder::default();
+    let indexed = matches!(table_type, TableType::Default);
+    let render = render();
+    let markdown = matches!(render.style, TableStyle::Markdown);
+    let columns: Vec<usize> = (0..header.len())
+        .filter(|&c| !view.hides(header[c]))
+        .collect();
 
-    builder.set_header(header.to_vec());
-    for row in table.iter() {
-        builder.push_record(row);
+    let mut names: Vec<&str> = columns.iter().map(|&c| header[c]).collect();
+    if indexed {
+        names.insert(0, "");
+    }
+    builder.set_header(names);
+    for i in view.rows(header, table) {
+        let mut record: Vec<String> = columns.iter().map(|&c| table[i][c].clone()).collect();
+        if indexed {
+            record.insert(0, i.to_string());
+        }
+        if markdown {
+            // Markdown cells can't span lines, and a "|" would end the cell.
+            record = record
+                .iter()
+                .map(|cell| cell.replace('|', "\\|").replace('\n', "<br>"))
+                .collect();
+        }
+        builder.push_record(record);
     }
 
-    let mut formatted_table = match table_type {
-        TableType::Default => builder.index().build(),
-        TableType::Cart => builder.build(),
-    };
-
-    let (TermWidth(term_width), _) = terminal_size().expect("Failed to get terminal size.");
+    let mut formatted_table = builder.build();
 
-    formatted_table
-        .with(Panel::header(title))
-        .with(Modify::new(object::Rows::first()).with(Alignment::center()))
-        .with(Style::rounded().horizontal('-'))
-        .with(style::BorderColor::filled(Color::FG_BLUE))
-        .with(Width::wrap(term_width as usize).priority::<peaker::PriorityMax>());
+    // Markdown tables can't have a title row, so the title is printed as a heading instead.
+    let title_rows = if markdown {
+        println!("### {}\n", title);
+        1
+    } else {
+        formatted_table.with(Panel::header(title));
+        formatted_table.with(Modify::new(object::Rows::first()).with(Alignment::center()));
+        2
+    };
+    match render.style {
+        TableStyle::Rounded => formatted_table.with(Style::rounded().horizontal('-')),
+        TableStyle::Plain => formatted_table.with(Style::ascii()),
+        TableStyle::Markdown => formatted_table.with(Style::markdown()),
+    };
+    if render.color {
+        formatted_table.with(style::BorderColor::filled(Color::FG_BLUE));
+    }
+    if !markdown {
+        formatted_table.with(Width::wrap(output_width()).priority::<peaker::PriorityMax>());
+    }
 
     match table_type {
-        TableType::Default => formatted_table.with(
-            Modify::new(object::Columns::first()).with(Format::content(|s| s.red().to_string())),
-        ),
+        TableType::Default => {
+            formatted_table.with(Modify::new(object::Columns::first()).with(Format::content(red)))
+        }
         TableType::Cart => {
-            formatted_table.with(
-                Modify::new(object::Rows::first()).with(Format::content(|s| s.red().to_string())),
-            );
-            // +2 Because of title and header
-            let subtotal_index = table.iter().position(|row| row[0] == "Subtotal").unwrap() + 2;
-            formatted_table.with(
-                Modify::new(object::Rows::new(subtotal_index..))
-                    .with(Format::content(|s| s.red().to_string())),
-            )
+            formatted_table.with(Modify::new(object::Rows::first()).with(Format::content(red)));
+            let subtotal_index =
+                table.iter().position(|row| row[0] == "Subtotal").unwrap() + title_rows;
+            formatted_table
+                .with(Modify::new(object::Rows::new(subtotal_index..)).with(Format::content(red)))
+        }
+        TableType::Info => {
+            formatted_table.with(Modify::new(object::Rows::first()).with(Format::content(red)))
         }
     };
 
     println!("{}", formatted_table);
 }
 
-pub fn select_operation() -> MenuOptions {
-    let and = Select::new(
+#[derive(Debug, Clone)]
+pub enum RowFilter {
+    Text(String),
+    Pattern(Regex),
+}
+
+impl RowFilter {
+    /// Substring filter, or a regular expression when prefixed with "re:".
+    pub fn parse(input: &str) -> Result<RowFilter, String> {
+        match input.strip_prefix("re:") {
+            Some(pattern) => Regex::new(pattern)
+                .map(RowFilter::Pattern)
+                .map_err(|e| e.to_string()),
+            None => Ok(RowFilter::Text(input.to_lowercase())),
+        }
+    }
+
+    fn matches(&self, cell: &str) -> bool {
+        match self {
+            RowFilter::Text(text) => cell.to_lowercase().contains(text),
+            RowFilter::Pattern(regex) => regex.is_match(cell),
+        }
+    }
+}
+
+/// How a table is shown: which rows, in which order, and without which columns.
+#[derive(Debug, Clone, Default)]
+pub struct TableView {
+    /// Column name and whether the order is descending.
+    pub sort: Option<(String, bool)>,
+    pub filter: Option<RowFilter>,
+    pub hidden: Vec<String>,
+}
+
+enum ViewOptions {
+    Sort,
+    Filter,
+    Columns,
+    Reset,
+}
+
+impl std::fmt::Display for ViewOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ViewOptions::Sort => write!(f, "Sort"),
+            ViewOptions::Filter => write!(f, "Filter"),
+            ViewOptions::Columns => write!(f, "Hide Columns"),
+            ViewOptions::Reset => write!(f, "Reset"),
+        }
+    }
+}
+
+fn column_index(header: &[&str], column: &str) -> Option<usize> {
+    header.iter().position(|h| h.eq_ignore_ascii_case(column))
+}
+
+impl TableView {
+    pub fn hides(&self, column: &str) -> bool {
+        self.hidden.iter().any(|h| h.eq_ignore_ascii_case(column))
+    }
+
+    /// Whether a column shows the notes of wantlist items, or something read from them.
+    pub fn shows_notes(&self) -> bool {
+        ["Notes", "Max", "Budget"].iter().any(|c| !self.hides(c))
+    }
+
+    /// Indices of the rows of `table` to show, in display order. Columns that are not part of
+    /// `header` are ignored, so the same view can be applied to every table.
+    pub fn rows(&self, header: &[&str], table: &[Vec<String>]) -> Vec<usize> {
+        let mut rows: Vec<usize> = (0..table.len())
+            .filter(|&i| match &self.filter {
+                Some(filter) => table[i].iter().any(|cell| filter.matches(cell)),
+                None => true,
+            })
+            .collect();
+        if let Some((column, descending)) = &self.sort {
+            if let Some(c) = column_index(header, column) {
+                rows.sort_by(|&a, &b| {
+                    let ordering = compare_cells(header[c], &table[a][c], &table[b][c]);
+                    match descending {
+                        true => ordering.reverse(),
+                        false => ordering,
+                    }
+                });
+            }
+        }
+        rows
+    }
+
+    /// Asks how to change the view of a table with the given columns.
+    pub fn edit(&mut self, header: &[&str]) {
+        let options = vec![
+            ViewOptions::Sort,
+            ViewOptions::Filter,
+            ViewOptions::Columns,
+            ViewOptions::Reset,
+        ];
+        let Ok(option) = Select::new("View:", options).prompt() else {
+            return;
+        };
+        let columns: Vec<String> = header.iter().map(|h| h.to_string()).collect();
+        match option {
+            ViewOptions::Sort => {
+                let Ok(column) = Select::new("Sort by:", columns).prompt() else {
+                    return;
+                };
+                let descending = Select::new("Order:", vec!["Ascending", "Descending"])
+                    .prompt()
+                    .is_ok_and(|order| order == "Descending");
+                self.sort = Some((column, descending));
+            }
+            ViewOptions::Filter => {
+                let input = Text::new("Filter rows (re: for a regex, empty to clear):")
+                    .with_validator(|input: &str| match RowFilter::parse(input) {
+                        Ok(_) => Ok(Validation::Valid),
+                        Err(e) => Ok(Validation::Invalid(e.into())),
+                    })
+                    .prompt()
+                    .unwrap_or_default();
+                self.filter = match input.is_empty() {
+                    true => None,
+                    false => RowFilter::parse(&input).ok(),
+                };
+            }
+            ViewOptions::Columns => {
+                let hidden: Vec<usize> = (0..columns.len())
+                    .filter(|&c| self.hides(&columns[c]))
+                    .collect();
+                if let Ok(hidden) = MultiSelect::new("Hide:", columns)
+                    .with_default(&hidden)
+                    .prompt()
+                {
+                    self.hidden = hidden;
+                }
+            }
+            ViewOptions::Reset => *self = TableView::default(),
+        }
+    }
+}
+
+/// Like `select_operation`, with the option to change how the table is shown.
+pub fn select_table_operation() -> MenuOptions {
+    Select::new(
         "Select:",
         vec![
-            MenuOptions::SelectId,
+            MenuOptions::Select,
+            MenuOptions::View,
             MenuOptions::GoBack,
             MenuOptions::Exit,
         ],
     )
-    .prompt();
-    and.unwrap()
+    .prompt()
+    .unwrap_or(MenuOptions::Exit)
+}
+
+/// Like `select_table_operation`, with the options to edit the notes of a wantlist item and to
+/// show its release.
+pub fn select_wantlist_operation() -> WantlistMenu {
+    Select::new(
+        "Select:",
+        vec![
+            WantlistMenu::Select,
+            WantlistMenu::View,
+            WantlistMenu::Notes,
+            WantlistMenu::Details,
+            WantlistMenu::GoBack,
+            WantlistMenu::Exit,
+        ],
+    )
+    .prompt()
+    .unwrap_or(WantlistMenu::Exit)
+}
+
+/// Text typed by the user, starting from `current`. Cancelling keeps `current`.
+pub fn edit_text(request: &str, current: &str) -> String {
+    Text::new(request)
+        .with_initial_value(current)
+        .prompt()
+        .unwrap_or(current.to_string())
+}
+
+/// Rating from 1 to 5 chosen by the user, 0 for none. Cancelling keeps `current`.
+pub fn select_rating(current: u8) -> u8 {
+    let options = vec!["No rating", "1", "2", "3", "4", "5"];
+    Select::new("Rating:", options)
+        .with_starting_cursor(current as usize)
+        .raw_prompt()
+        .map_or(current, |option| option.index as u8)
 }
 
-pub fn ask_id(len: usize, request: &str) -> usize {
-    let selection = CustomType::<usize>::new(request)
-        .with_validator(move |input: &usize| {
-            if (0..len).contains(input) {
-                Ok(Validation::Valid)
-            } else {
-                Ok(Validation::Invalid("Input outside of index range.".into()))
+/// Asks a yes/no question, answering no when there is no terminal to ask on.
+pub fn confirm(request: &str) -> bool {
+    Confirm::new(request)
+        .with_default(false)
+        .prompt()
+        .unwrap_or(false)
+}
+
+/// Prints the marketplace statistics and price suggestions of a release.
+pub fn print_price_stats(stats: &PriceStats) {
+    let show = |price: &Option<Price>| match price {
+        Some(price) => price.to_string(),
+        None => "-".to_string(),
+    };
+    let row = vec![
+        show(&stats.lowest),
+        stats.for_sale.map_or("-".to_string(), |n| n.to_string()),
+        show(&stats.sold_low),
+        show(&stats.sold_median),
+        show(&stats.sold_high),
+        stats.last_sold.clone().unwrap_or("-".to_string()),
+    ];
+    print_table(STATS_HEADER, &[row], "Marketplace", TableType::Info);
+    if !stats.suggestions.is_empty() {
+        let row = SUGGESTIONS_HEADER
+            .iter()
+            .map(|grade| {
+                let condition: Condition = grade.parse().unwrap();
+                show(&stats.suggestion(condition).cloned())
+            })
+            .collect();
+        print_table(
+            SUGGESTIONS_HEADER,
+            &[row],
+            "Price Suggestions",
+            TableType::Info,
+        );
+    }
+}
+
+/// Link of the master release chosen among the search results for `search`.
+pub fn select_master(scraper: &DiscogsScraper, search: &str) -> String {
+    let (links, table) = scraper.search_release(search);
+    print_table(
+        RELEASE_HEADER,
+        &table,
+        "Master Releases",
+        TableType::Default,
+    );
+    let selected_index = match select_operation() {
+        MenuOptions::Select => {
+            match select_row(
+                "Select a release:",
+                RELEASE_HEADER,
+                &table,
+                &TableView::default(),
+            ) {
+                Some(index) => index,
+                None => std::process::exit(0),
             }
+        }
+        _ => std::process::exit(0),
+    };
+    links[selected_index].clone()
+}
+
+pub fn select_operation() -> MenuOptions {
+    let and = Select::new(
+        "Select:",
+        vec![MenuOptions::Select, MenuOptions::GoBack, MenuOptions::Exit],
+    )
+    .prompt();
+    and.unwrap_or(MenuOptions::Exit)
+}
+
+/// Cart actions. Without a terminal to prompt on, the cart is only printed.
+pub fn select_cart_action() -> CartMenu {
+    Select::new(
+        "Select:",
+        vec![
+            CartMenu::RemoveItem,
+            CartMenu::EmptyOrder,
+            CartMenu::ChangeShipping,
+            CartMenu::MoveItem,
+            CartMenu::Exit,
+        ],
+    )
+    .prompt()
+    .unwrap_or(CartMenu::Exit)
+}
+
+pub fn select_version_action() -> VersionMenu {
+    Select::new(
+        "Select:",
+        vec![
+            VersionMenu::AddToWantlist,
+            VersionMenu::Listings,
+            VersionMenu::Details,
+            VersionMenu::View,
+            VersionMenu::Exit,
+        ],
+    )
+    .prompt()
+    .unwrap_or(VersionMenu::Exit)
+}
+
+/// Actions on a search result, which depend on its type.
+pub fn select_search_action(kind: &str) -> SearchAction {
+    let mut actions = match kind {
+        "release" => vec![
+            SearchAction::AddToWantlist,
+            SearchAction::Listings,
+            SearchAction::Details,
+        ],
+        "master" => vec![SearchAction::AddLps, SearchAction::Versions],
+        "artist" | "label" => vec![SearchAction::Discography],
+        _ => Vec::new(),
+    };
+    actions.extend([SearchAction::Link, SearchAction::GoBack]);
+    Select::new("Select:", actions)
+        .prompt()
+        .unwrap_or(SearchAction::GoBack)
+}
+
+const PAGE_SIZE: usize = 15;
+
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle.chars().all(|c| haystack.any(|h| h == c))
+}
+
+/// Fuzzy match: every word typed appears in the option with its letters in order, not
+/// necessarily adjacent. Typing a number picks the row with that index instead.
+fn fuzzy_filter(input: &str, _: &String, option: &str, index: usize) -> bool {
+    if let Ok(id) = input.trim().parse::<usize>() {
+        return match option.starts_with('[') {
+            true => option.starts_with(&format!("[{}]", id)),
+            false => id == index,
+        };
+    }
+    let option = option.to_lowercase();
+    input
+        .to_lowercase()
+        .split_whitespace()
+        .all(|word| is_subsequence(word, &option))
+}
+
+/// One line per row, prefixed by the row index shown by `print_table`. Links are left out.
+fn row_labels(table: &[Vec<String>], rows: &[usize]) -> Vec<String> {
+    rows.iter()
+        .map(|&i| {
+            let cells = table[i]
+                .iter()
+                .flat_map(|cell| cell.lines())
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with("http"))
+                .join(" | ");
+            format!("[{}] {}", i, cells)
+        })
+        .collect()
+}
+
+/// Index of the chosen option, or `None` if the prompt was cancelled.
+pub fn select_index(request: &str, options: Vec<String>) -> Option<usize> {
+    Select::new(request, options)
+        .with_filter(&fuzzy_filter)
+        .with_page_size(PAGE_SIZE)
+        .raw_prompt()
+        .ok()
+        .map(|option| option.index)
+}
+
+/// Row of `table` chosen among those shown by `view`.
+pub fn select_row(
+    request: &str,
+    header: &[&str],
+    table: &[Vec<String>],
+    view: &TableView,
+) -> Option<usize> {
+    let rows = view.rows(header, table);
+    select_index(request, row_labels(table, &rows)).map(|i| rows[i])
+}
+
+/// Rows of `table` chosen among those shown by `view`, empty if the prompt was cancelled.
+pub fn select_rows(
+    request: &str,
+    header: &[&str],
+    table: &[Vec<String>],
+    view: &TableView,
+) -> Vec<usize> {
+    let rows = view.rows(header, table);
+    MultiSelect::new(request, row_labels(table, &rows))
+        .with_filter(&fuzzy_filter)
+        .with_page_size(PAGE_SIZE)
+        .raw_prompt()
+        .map(|options| {
+            options
+                .into_iter()
+                .map(|option| rows[option.index])
+                .collect()
         })
-        .prompt();
-    selection.unwrap()
+        .unwrap_or_default()
 }
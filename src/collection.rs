@@ -0,0 +1,255 @@
+use crate::cli::{self, CollectionCommands, TableType, TableView};
+use crate::web::{CollectionField, CollectionItem, DiscogsScraper, Folder};
+use itertools::Itertools;
+
+const FOLDERS_HEADER: &[&str] = &["ID", "Folder", "Items"];
+const ORDER_HEADER: &[&str] = &["Release", "Title"];
+const RECEIVE_HEADER: &[&str] = &["Release", "Collection", "Wantlist"];
+const COLLECTION_HEADER: &[&str] = &[
+    "Release", "Artist", "Title", "Format", "Year", "Folder", "Rating", "Notes",
+];
+
+fn find_folder<'a>(folders: &'a [Folder], folder: &str) -> &'a Folder {
+    let found = folders
+        .iter()
+        .find(|f| f.id.to_string() == folder || f.name.eq_ignore_ascii_case(folder));
+    match found {
+        Some(folder) => folder,
+        None => {
+            println!("No folder {} in your collection", folder);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn folder_name(folders: &[Folder], id: u64) -> String {
+    folders
+        .iter()
+        .find(|f| f.id == id)
+        .map_or(id.to_string(), |f| f.name.clone())
+}
+
+/// The copy of a release to act on, asking which one when the collection holds several.
+fn find_instance(scraper: &DiscogsScraper, folders: &[Folder], release_id: u64) -> CollectionItem {
+    let mut instances = scraper.collection_instances(release_id);
+    if instances.is_empty() {
+        println!("Release {} is not in your collection", release_id);
+        std::process::exit(1);
+    }
+    if instances.len() == 1 {
+        return instances.remove(0);
+    }
+    let labels = instances
+        .iter()
+        .map(|item| {
+            format!(
+                "{} | {}",
+                item.label(),
+                folder_name(folders, item.folder_id)
+            )
+        })
+        .collect();
+    match cli::select_index("Select a copy:", labels) {
+        Some(index) => instances.remove(index),
+        None => std::process::exit(0),
+    }
+}
+
+fn items_table(
+    items: &[CollectionItem],
+    folders: &[Folder],
+    fields: &[CollectionField],
+) -> Vec<Vec<String>> {
+    items
+        .iter()
+        .map(|item| {
+            let notes = item
+                .notes
+                .iter()
+                .filter(|note| !note.value.is_empty())
+                .map(|note| {
+                    let name = fields
+                        .iter()
+                        .find(|f| f.id == note.field_id)
+                        .map_or(note.field_id.to_string(), |f| f.name.clone());
+                    format!("{}: {}", name, note.value)
+                })
+                .join("\n");
+            let info = &item.basic_information;
+            vec![
+                item.id.to_string(),
+                info.get_artists(),
+                info.title.clone(),
+                info.get_formats(),
+                match info.year {
+                    0 => String::new(),
+                    year => year.to_string(),
+                },
+                folder_name(folders, item.folder_id),
+                match item.rating {
+                    0 => String::new(),
+                    rating => rating.to_string(),
+                },
+                notes,
+            ]
+        })
+        .collect()
+}
+
+/// Adds each release to the folder and, once it is there, removes it from the wantlist. A release
+/// that couldn't be added stays in the wantlist.
+fn receive(scraper: &DiscogsScraper, folder: &Folder, release_ids: &[u64]) {
+    let mut table: Vec<Vec<String>> = Vec::new();
+    for &release_id in release_ids {
+        let (collection, wantlist) = match scraper.add_to_collection(folder.id, release_id) {
+            None => (String::from("Failed"), String::from("Kept")),
+            Some(_) => {
+                let wantlist = match scraper.remove_releases_from_wantlist(vec![release_id as i64])
+                {
+                    Ok(true) => String::from("Removed"),
+                    Ok(false) => String::from("Failed"),
+                    Err(messages) => messages.join("\n"),
+                };
+                (format!("Added to {}", folder.name), wantlist)
+            }
+        };
+        table.push(vec![release_id.to_string(), collection, wantlist]);
+    }
+    cli::print_table(RECEIVE_HEADER, &table, "Received", TableType::Default);
+}
+
+fn report(done: bool, success: &str, failure: &str) {
+    match done {
+        true => println!("{}", success),
+        false => println!("{}", failure),
+    }
+}
+
+pub fn collection_command(scraper: DiscogsScraper, command: CollectionCommands, view: &TableView) {
+    let folders = scraper.collection_folders();
+    match command {
+        CollectionCommands::Folders => {
+            let table: Vec<Vec<String>> = folders
+                .iter()
+                .map(|f| vec![f.id.to_string(), f.name.clone(), f.count.to_string()])
+                .collect();
+            cli::print_view(
+                FOLDERS_HEADER,
+                &table,
+                "Collection Folders",
+                TableType::Default,
+                view,
+            );
+        }
+        CollectionCommands::List { query, folder } => {
+            let (folder_id, title) = match &folder {
+                Some(folder) => {
+                    let folder = find_folder(&folders, folder);
+                    (folder.id, folder.name.clone())
+                }
+                None => (0, String::from("Collection")),
+            };
+            let mut items = scraper.collection_items(folder_id);
+            if let Some(query) = query {
+                let query = query.to_lowercase();
+                items.retain(|item| item.label().to_lowercase().contains(&query));
+            }
+            let fields = scraper.collection_fields();
+            let table = items_table(&items, &folders, &fields);
+            cli::print_view(COLLECTION_HEADER, &table, &title, TableType::Default, view);
+        }
+        CollectionCommands::Add { release_id, folder } => {
+            let folder = find_folder(&folders, &folder);
+            report(
+                scraper.add_to_collection(folder.id, release_id).is_some(),
+                &format!("Added release {} to {}", release_id, folder.name),
+                &format!("Unable to add release {} to {}", release_id, folder.name),
+            );
+        }
+        CollectionCommands::Move { release_id, folder } => {
+            let item = find_instance(&scraper, &folders, release_id);
+            let folder = find_folder(&folders, &folder);
+            report(
+                scraper.move_collection_item(&item, folder.id),
+                &format!("Moved {} to {}", item.label(), folder.name),
+                &format!("Unable to move {}", item.label()),
+            );
+        }
+        CollectionCommands::Remove { release_id } => {
+            let item = find_instance(&scraper, &folders, release_id);
+            report(
+                scraper.remove_from_collection(&item),
+                &format!("Removed {}", item.label()),
+                &format!("Unable to remove {}", item.label()),
+            );
+        }
+        CollectionCommands::Rate { release_id, rating } => {
+            let item = find_instance(&scraper, &folders, release_id);
+            report(
+                scraper.rate_collection_item(&item, rating),
+                &format!("Rated {} {}/5", item.label(), rating),
+                &format!("Unable to rate {}", item.label()),
+            );
+        }
+        CollectionCommands::Receive {
+            mut release_ids,
+            order,
+            folder,
+        } => {
+            let folder = find_folder(&folders, &folder);
+            if let Some(order) = order {
+                let releases: Vec<(u64, String)> = scraper
+                    .get_order(&order)
+                    .map_or(Vec::new(), |order| order.items)
+                    .into_iter()
+                    .map(|item| (item.release_id, item.description))
+                    .unique_by(|(id, _)| *id)
+                    .collect();
+                if releases.is_empty() {
+                    println!("No releases found in order {}", order);
+                } else {
+                    let table: Vec<Vec<String>> = releases
+                        .iter()
+                        .map(|(id, title)| vec![id.to_string(), title.clone()])
+                        .collect();
+                    cli::print_table(
+                        ORDER_HEADER,
+                        &table,
+                        &format!("Order {}", order),
+                        TableType::Info,
+                    );
+                    if !cli::confirm("Receive these releases?") {
+                        return;
+                    }
+                }
+                release_ids.extend(releases.into_iter().map(|(id, _)| id));
+                release_ids = release_ids.into_iter().unique().collect();
+            }
+            receive(&scraper, folder, &release_ids);
+        }
+        CollectionCommands::Field {
+            release_id,
+            field,
+            value,
+        } => {
+            let fields = scraper.collection_fields();
+            let Some(field) = fields
+                .iter()
+                .find(|f| f.id.to_string() == field || f.name.eq_ignore_ascii_case(&field))
+            else {
+                println!(
+                    "No field {} in your collection, fields are: {}",
+                    field,
+                    fields.iter().map(|f| &f.name).join(", ")
+                );
+                std::process::exit(1);
+            };
+            let item = find_instance(&scraper, &folders, release_id);
+            report(
+                scraper.edit_collection_field(&item, field.id, &value),
+                &format!("Set {} of {} to \"{}\"", field.name, item.label(), value),
+                &format!("Unable to set {} of {}", field.name, item.label()),
+            );
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use crate::web::{CartOrder, Listing, Price, WantlistItem};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS pages (
+        url TEXT PRIMARY KEY,
+        body TEXT NOT NULL,
+        fetched_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS wantlist (
+        release_id INTEGER,
+        title TEXT NOT NULL,
+        for_sale TEXT NOT NULL,
+        format TEXT NOT NULL,
+        year TEXT NOT NULL,
+        scraped_at INTEGER NOT NULL,
+        UNIQUE (release_id, title, scraped_at)
+    );
+    CREATE TABLE IF NOT EXISTS listings (
+        listing_id TEXT NOT NULL,
+        release_id INTEGER,
+        seller TEXT NOT NULL,
+        media TEXT,
+        sleeve TEXT,
+        price REAL,
+        currency TEXT,
+        scraped_at INTEGER NOT NULL,
+        UNIQUE (listing_id, scraped_at)
+    );
+    CREATE TABLE IF NOT EXISTS sellers (
+        name TEXT NOT NULL,
+        wanted INTEGER,
+        scraped_at INTEGER NOT NULL,
+        UNIQUE (name, scraped_at)
+    );
+    CREATE TABLE IF NOT EXISTS cart (
+        seller TEXT NOT NULL,
+        description TEXT NOT NULL,
+        price TEXT NOT NULL,
+        scraped_at INTEGER NOT NULL
+    );
+";
+
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+/// Local record of scraped pages and of the wantlist, listings, sellers and cart found in them.
+/// Records are keyed by the time their page was fetched, so parsing a cached page twice does not
+/// duplicate them.
+#[derive(Debug)]
+pub struct Database {
+    connection: Connection,
+    ttl: i64,
+}
+
+/// Asking prices of a release seen on one day, in one currency.
+pub struct PriceTrend {
+    pub day: String,
+    pub currency: String,
+    pub listings: usize,
+    pub lowest: f64,
+    pub average: f64,
+    pub highest: f64,
+}
+
+impl Database {
+    /// Opens (or creates) the database at `path`. Pages fetched less than `ttl` seconds ago are
+    /// served from it instead of being scraped again.
+    pub fn open(path: &str, ttl: i64) -> Database {
+        let connection = Connection::open(path).expect("Unable to open database.");
+        connection
+            .execute_batch(SCHEMA)
+            .expect("Unable to create database tables.");
+        Database { connection, ttl }
+    }
+
+    pub fn cached_page(&self, url: &str) -> Option<(String, i64)> {
+        self.connection
+            .query_row(
+                "SELECT body, fetched_at FROM pages WHERE url = ?1 AND fetched_at >= ?2",
+                params![url, now() - self.ttl],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .expect("Unable to read cached page.")
+    }
+
+    pub fn store_page(&self, url: &str, body: &str, fetched_at: i64) {
+        self.connection
+            .execute(
+                "INSERT OR REPLACE INTO pages (url, body, fetched_at) VALUES (?1, ?2, ?3)",
+                params![url, body, fetched_at],
+            )
+            .expect("Unable to cache page.");
+    }
+
+    /// Drops the cached pages whose URL starts with `prefix`, so they are scraped again.
+    pub fn forget_pages(&self, prefix: &str) {
+        self.connection
+            .execute(
+                "DELETE FROM pages WHERE substr(url, 1, length(?1)) = ?1",
+                params![prefix],
+            )
+            .expect("Unable to clear cached pages.");
+    }
+
+    pub fn record_wantlist(&self, items: &[WantlistItem], scraped_at: i64) {
+        for item in items {
+            self.connection
+                .execute(
+                    "INSERT OR IGNORE INTO wantlist (release_id, title, for_sale, format, year, scraped_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        item.release_id,
+                        item.title,
+                        item.for_sale,
+                        item.format,
+                        item.year,
+                        scraped_at
+                    ],
+                )
+                .expect("Unable to record wantlist item.");
+        }
+    }
+
+    pub fn record_listings(&self, release_id: Option<u64>, listings: &[Listing], scraped_at: i64) {
+        for listing in listings {
+            let price = Price::parse(&listing.price);
+            self.connection
+                .execute(
+                    "INSERT OR IGNORE INTO listings
+                     (listing_id, release_id, seller, media, sleeve, price, currency, scraped_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![
+                        listing.id,
+                        release_id,
+                        listing.seller,
+                        listing.grading.media.map(|c| c.to_string()),
+                        listing.grading.sleeve.map(|c| c.to_string()),
+                        price.as_ref().map(|p| p.value),
+                        price.map(|p| p.currency),
+                        scraped_at
+                    ],
+                )
+                .expect("Unable to record listing.");
+            self.connection
+                .execute(
+                    "INSERT OR IGNORE INTO sellers (name, wanted, scraped_at) VALUES (?1, ?2, ?3)",
+                    params![listing.seller, listing.wanted, scraped_at],
+                )
+                .expect("Unable to record seller.");
+        }
+    }
+
+    /// Records the items of each order, leaving out the subtotal and shipping rows of its table.
+    pub fn record_cart(&self, orders: &[CartOrder]) {
+        let scraped_at = now();
+        for order in orders {
+            for item in order.items.iter() {
+                let description = format!(
+                    "{}\n{}\n{}{}",
+                    item.name,
+                    item.grading,
+                    crate::web::WEB_HOME_URL,
+                    item.link
+                );
+                self.connection
+                    .execute(
+                        "INSERT INTO cart (seller, description, price, scraped_at)
+                         VALUES (?1, ?2, ?3, ?4)",
+                        params![order.title(), description, item.price, scraped_at],
+                    )
+                    .expect("Unable to record cart.");
+            }
+        }
+    }
+
+    pub fn price_history(&self, release_id: u64) -> Vec<PriceTrend> {
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT date(scraped_at, 'unixepoch') AS day, currency,
+                        COUNT(DISTINCT listing_id), MIN(price), AVG(price), MAX(price)
+                 FROM listings
+                 WHERE release_id = ?1 AND price IS NOT NULL
+                 GROUP BY day, currency
+                 ORDER BY day, currency",
+            )
+            .unwrap();
+        statement
+            .query_map(params![release_id], |row| {
+                Ok(PriceTrend {
+                    day: row.get(0)?,
+                    currency: row.get(1)?,
+                    listings: row.get(2)?,
+                    lowest: row.get(3)?,
+                    average: row.get(4)?,
+                    highest: row.get(5)?,
+                })
+            })
+            .expect("Unable to read price history.")
+            .map(Result::unwrap)
+            .collect()
+    }
+}
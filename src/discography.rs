@@ -0,0 +1,176 @@
+use crate::cli::{self, DiscographyKind, MenuOptions, TableType, TableView};
+use crate::web::{DiscographyItem, DiscogsScraper, SearchQuery};
+use itertools::Itertools;
+
+const DISCOGRAPHY_HEADER: &[&str] = &[
+    "Type", "Year", "Artist", "Title", "Format", "Label", "Catalog#", "Role",
+];
+
+/// Which items of a discography are offered for the wantlist.
+pub struct DiscographyFilter {
+    pub format: Option<String>,
+    pub from: Option<u32>,
+    pub to: Option<u32>,
+    pub roles: Vec<String>,
+}
+
+impl Default for DiscographyFilter {
+    fn default() -> DiscographyFilter {
+        DiscographyFilter {
+            format: None,
+            from: None,
+            to: None,
+            roles: vec![String::from("Main")],
+        }
+    }
+}
+
+impl DiscographyFilter {
+    /// Masters have no format of their own and label releases no role, so those checks only
+    /// apply to the items that have one. Items without a year are dropped when a year range is
+    /// given.
+    fn keeps(&self, item: &DiscographyItem) -> bool {
+        let format = match (&self.format, &item.format) {
+            (Some(wanted), Some(format)) => format.to_lowercase().contains(&wanted.to_lowercase()),
+            _ => true,
+        };
+        let year = item.year.unwrap_or(0);
+        let years = (self.from.is_none() && self.to.is_none())
+            || (year > 0
+                && self.from.is_none_or(|from| year >= from)
+                && self.to.is_none_or(|to| year <= to));
+        let role = item
+            .role
+            .as_ref()
+            .is_none_or(|role| self.roles.iter().any(|r| r.eq_ignore_ascii_case(role)));
+        format && years && role
+    }
+
+    /// Releases to add for an item. With a format filter, a master adds its versions in that
+    /// format rather than its main release, which is often in another one.
+    fn release_ids(&self, scraper: &DiscogsScraper, item: &DiscographyItem) -> Vec<u64> {
+        let Some(wanted) = self.format.as_ref().filter(|_| item.is_master()) else {
+            return vec![item.release_id()];
+        };
+        let lowercase = wanted.to_lowercase();
+        let (ids, versions) = scraper.get_master_versions(item.id);
+        let ids: Vec<u64> = ids
+            .into_iter()
+            .zip(versions)
+            // The second column of a version is its format.
+            .filter(|(_, version)| version[1].to_lowercase().contains(&lowercase))
+            .map(|(id, _)| id)
+            .collect();
+        if ids.is_empty() {
+            println!("No {} versions of {}", wanted, item.title);
+        }
+        ids
+    }
+}
+
+/// Id of the artist or label `name`, asking which one when the search finds several. With
+/// `is_id` the name is the id itself, as names can be numbers too.
+fn find_id(scraper: &DiscogsScraper, kind: &str, name: &str, is_id: bool) -> u64 {
+    if is_id {
+        return name.parse().unwrap_or_else(|_| {
+            println!("{} is not a valid {} id", name, kind);
+            std::process::exit(1);
+        });
+    }
+    let query = SearchQuery {
+        query: Some(name.to_string()),
+        kind: kind.to_string(),
+        ..Default::default()
+    };
+    let results = scraper.search_database(&query, 1);
+    match results.len() {
+        0 => {
+            println!("No {} found for {}", kind, name);
+            std::process::exit(1);
+        }
+        1 => results[0].id,
+        _ => {
+            let labels = results.iter().map(|r| r.title.clone()).collect();
+            match cli::select_index(&format!("Select the {}:", kind), labels) {
+                Some(index) => results[index].id,
+                None => std::process::exit(0),
+            }
+        }
+    }
+}
+
+/// Previews the discography of an artist or label ("artist" or "label" for `kind`) and adds the
+/// selected items to the wantlist, masters through their main release or, with a format filter,
+/// their versions in that format.
+pub fn discography_to_wantlist(
+    scraper: &DiscogsScraper,
+    kind: &str,
+    id: u64,
+    filter: &DiscographyFilter,
+    view: &TableView,
+) {
+    let mut items = scraper.get_discography(&format!("{}s/{}/releases", kind, id));
+    items.retain(|item| filter.keeps(item));
+    if items.is_empty() {
+        println!("No items of {} {} match the filters", kind, id);
+        return;
+    }
+    let table: Vec<Vec<String>> = items.iter().map(DiscographyItem::row).collect();
+    let mut discography_view = view.clone();
+    let mut print_discography = true;
+    loop {
+        if print_discography {
+            cli::print_view(
+                DISCOGRAPHY_HEADER,
+                &table,
+                "Discography",
+                TableType::Default,
+                &discography_view,
+            );
+        }
+        print_discography = false;
+        match cli::select_table_operation() {
+            MenuOptions::Select => {
+                let selected = cli::select_rows(
+                    "Select items to add:",
+                    DISCOGRAPHY_HEADER,
+                    &table,
+                    &discography_view,
+                );
+                if selected.is_empty() {
+                    continue;
+                }
+                let ids: Vec<i64> = selected
+                    .iter()
+                    .flat_map(|&i| filter.release_ids(scraper, &items[i]))
+                    .map(|id| id as i64)
+                    .unique()
+                    .collect();
+                if ids.is_empty() {
+                    continue;
+                }
+                match scraper.add_releases_to_wantlist(ids) {
+                    Ok(added) => println!("Added {} items to wantlist.", added),
+                    Err(messages) => println!("{:#?}", messages),
+                }
+            }
+            MenuOptions::View => {
+                discography_view.edit(DISCOGRAPHY_HEADER);
+                print_discography = true;
+            }
+            MenuOptions::GoBack | MenuOptions::Exit => return,
+        }
+    }
+}
+
+pub fn discography_command(
+    scraper: DiscogsScraper,
+    kind: DiscographyKind,
+    name: &str,
+    is_id: bool,
+    filter: DiscographyFilter,
+    view: &TableView,
+) {
+    let id = find_id(&scraper, kind.as_str(), name, is_id);
+    discography_to_wantlist(&scraper, kind.as_str(), id, &filter, view);
+}
@@ -0,0 +1,204 @@
+use crate::cli::{self, InventoryCommands, PriceChange, TableType, TableView};
+use crate::web::{Condition, DiscogsScraper, Grading, InventoryListing, Price};
+use serde::Deserialize;
+
+const INVENTORY_HEADER: &[&str] = &[
+    "Listing",
+    "Release",
+    "Description",
+    "Condition",
+    "Price",
+    "Status",
+    "Posted",
+];
+const CREATED_HEADER: &[&str] = &["Release", "Condition", "Price", "Listing"];
+const REPRICED_HEADER: &[&str] = &["Listing", "Description", "Price", "New Price", "Result"];
+const DELETED_HEADER: &[&str] = &["Listing", "Result"];
+
+/// A row of the CSV file given to `inventory create`.
+#[derive(Debug, Deserialize)]
+struct NewListing {
+    release_id: u64,
+    condition: String,
+    #[serde(default)]
+    sleeve_condition: Option<String>,
+    price: f32,
+    #[serde(default)]
+    comments: String,
+}
+
+fn parse_condition(condition: &str) -> Condition {
+    condition.parse().unwrap_or_else(|e: String| {
+        println!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+fn list_inventory(scraper: &DiscogsScraper, query: Option<String>, view: &TableView) {
+    let mut listings = scraper.get_inventory();
+    if let Some(query) = query {
+        let query = query.to_lowercase();
+        listings.retain(|listing| {
+            listing.release.description.to_lowercase().contains(&query)
+                || listing.comments.to_lowercase().contains(&query)
+        });
+    }
+    let table: Vec<Vec<String>> = listings
+        .iter()
+        .map(|listing| {
+            let condition = match &listing.sleeve_condition {
+                Some(sleeve) => format!("{}\n{}", listing.condition, sleeve),
+                None => listing.condition.clone(),
+            };
+            vec![
+                listing.id.to_string(),
+                listing.release.id.to_string(),
+                listing.release.description.clone(),
+                condition,
+                listing.price.to_string(),
+                listing.status.clone(),
+                listing.posted.clone(),
+            ]
+        })
+        .collect();
+    cli::print_view(
+        INVENTORY_HEADER,
+        &table,
+        "Inventory",
+        TableType::Default,
+        view,
+    );
+}
+
+fn create_listings(scraper: &DiscogsScraper, file: &str) {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_path(file)
+        .expect("Unable to read CSV file.");
+    // Every row is checked before anything is listed, so a typo doesn't leave half the file
+    // on sale.
+    let rows: Vec<(NewListing, Grading)> = reader
+        .deserialize()
+        .map(|row| {
+            let row: NewListing = row.unwrap_or_else(|e| {
+                println!("Invalid row in {}: {}", file, e);
+                std::process::exit(1);
+            });
+            let grading = Grading {
+                media: Some(parse_condition(&row.condition)),
+                sleeve: row.sleeve_condition.as_deref().map(parse_condition),
+            };
+            (row, grading)
+        })
+        .collect();
+    let table: Vec<Vec<String>> = rows
+        .iter()
+        .map(|(row, grading)| {
+            let result =
+                match scraper.create_listing(row.release_id, grading, row.price, &row.comments) {
+                    Ok(listing_id) => listing_id.to_string(),
+                    Err(e) => format!("Failed: {}", e),
+                };
+            vec![
+                row.release_id.to_string(),
+                grading.to_string(),
+                format!("{:.2}", row.price),
+                result,
+            ]
+        })
+        .collect();
+    cli::print_table(
+        CREATED_HEADER,
+        &table,
+        "Created Listings",
+        TableType::Default,
+    );
+}
+
+/// Median sold price of a release, in the currency of `price`.
+fn median(scraper: &DiscogsScraper, release_id: u64, price: &Price) -> Option<f32> {
+    scraper
+        .get_price_stats(release_id)
+        .sold_median
+        .filter(|median| median.currency == price.currency)
+        .map(|median| median.value)
+}
+
+fn reprice(scraper: &DiscogsScraper, change: PriceChange, listing_ids: Vec<u64>, dry_run: bool) {
+    let listings: Vec<InventoryListing> = scraper
+        .get_inventory()
+        .into_iter()
+        .filter(|listing| match listing_ids.is_empty() {
+            true => listing.status == "For Sale",
+            false => listing_ids.contains(&listing.id),
+        })
+        .collect();
+    if listing_ids.is_empty()
+        && !dry_run
+        && !cli::confirm(&format!(
+            "Change the price of all {} listings for sale?",
+            listings.len()
+        ))
+    {
+        return;
+    }
+    let table: Vec<Vec<String>> = listings
+        .iter()
+        .map(|listing| {
+            let median = match change {
+                PriceChange::Median => median(scraper, listing.release.id, &listing.price),
+                _ => None,
+            };
+            let new = change.apply(listing.price.value, median);
+            let (new_price, result) = match new {
+                None => (String::from("-"), String::from("No price")),
+                Some(new) if dry_run => (format!("{:.2}", new), String::from("Preview")),
+                Some(new) => {
+                    let result = match scraper.set_listing_price(listing, new) {
+                        true => "Updated",
+                        false => "Failed",
+                    };
+                    (format!("{:.2}", new), result.to_string())
+                }
+            };
+            vec![
+                listing.id.to_string(),
+                listing.release.description.clone(),
+                listing.price.to_string(),
+                new_price,
+                result,
+            ]
+        })
+        .collect();
+    cli::print_table(REPRICED_HEADER, &table, "Prices", TableType::Default);
+}
+
+pub fn inventory_command(scraper: DiscogsScraper, command: InventoryCommands, view: &TableView) {
+    match command {
+        InventoryCommands::List { query } => list_inventory(&scraper, query, view),
+        InventoryCommands::Create { file } => create_listings(&scraper, &file),
+        InventoryCommands::Price {
+            change,
+            listing_ids,
+            dry_run,
+        } => reprice(&scraper, change, listing_ids, dry_run),
+        InventoryCommands::Delete { listing_ids } => {
+            let table: Vec<Vec<String>> = listing_ids
+                .iter()
+                .map(|&listing_id| {
+                    let result = match scraper.delete_listing(listing_id) {
+                        true => "Deleted",
+                        false => "Failed",
+                    };
+                    vec![listing_id.to_string(), result.to_string()]
+                })
+                .collect();
+            cli::print_table(
+                DELETED_HEADER,
+                &table,
+                "Deleted Listings",
+                TableType::Default,
+            );
+        }
+    }
+}
@@ -1,16 +1,33 @@
 mod cli;
+mod collection;
+mod db;
+mod discography;
+mod inventory;
+mod orders;
+mod rates;
+mod release;
+mod search;
+mod tui;
+mod versions;
+mod watch;
 mod web;
 
 use clap::Parser;
 use cli::Commands::*;
-use cli::{MenuOptions, TableType};
-use core::iter::zip;
+use cli::{CartCommands, CartMenu, MenuOptions, TableType, TableView, WantlistMenu};
+use owo_colors::{OwoColorize, Stream};
+use web::{CartItem, CartOrder, CartOutcome, ConditionFilter, WantlistItem};
 
-const WANTIST_HEADER: &[&str] = &["Seen", "Sellers", "Title", "Format", "Year"];
-const SELLERS_HEADER: &[&str] = &["Seller", "Amount", "Shipping From", "Condition", "Price"];
-const ITEMS_HEADER: &[&str] = &["Realease", "Condition", "Price"];
-const RELEASE_HEADER: &[&str] = &["Release", "Status", "Info", "Details"];
+const WANTIST_HEADER: &[&str] = &[
+    "Seen", "Sellers", "Title", "Format", "Year", "Notes", "Max", "Budget",
+];
+const NOTES_HEADER: &[&str] = &["Release", "Rating", "Notes"];
 const CART_HEADER: &[&str] = &["Description", "Price"];
+const CART_TOTAL_HEADER: &[&str] = &["Seller", "Items", "Subtotal", "Shipping", "Fees", "Total"];
+const MOVE_HEADER: &[&str] = &["Seller", "Shipping From", "Condition", "Price"];
+const HISTORY_HEADER: &[&str] = &[
+    "Day", "Currency", "Listings", "Lowest", "Average", "Highest",
+];
 
 #[derive(Debug)]
 enum WantlistOperations {
@@ -18,105 +35,668 @@ enum WantlistOperations {
     Remove,
 }
 
-fn check_wantlist(scraper: web::DiscogsScraper, query: Option<String>) {
-    let (links, mut table) = scraper.get_release(query);
+fn print_cart_outcome(outcome: &CartOutcome) {
+    match outcome {
+        CartOutcome::Added | CartOutcome::AlreadyInCart => println!(
+            "{}",
+            outcome.if_supports_color(Stream::Stdout, |o| o.green())
+        ),
+        _ => println!("{}", outcome.if_supports_color(Stream::Stdout, |o| o.red())),
+    }
+}
+
+/// "Seen" cell of a wantlist row: "=" for the exact release looked up, then "X" once its sellers
+/// were browsed.
+fn seen_marker(item: &WantlistItem, visited: bool) -> String {
+    let visited = match visited {
+        true => "X",
+        false => "",
+    };
+    match item.exact {
+        true => format!(
+            "{}{}",
+            "=".if_supports_color(Stream::Stdout, |s| s.green()),
+            visited
+        ),
+        false if visited.is_empty() => String::from(" "),
+        false => visited.to_string(),
+    }
+}
+
+fn check_wantlist(
+    scraper: web::DiscogsScraper,
+    mut items: Vec<WantlistItem>,
+    filter: ConditionFilter,
+    view: TableView,
+    hide_over_max: bool,
+) {
+    // The notes are read from the API, so only when a column or --hide-over-max needs them, and
+    // otherwise for the item that is edited or browsed.
+    let notes_loaded = hide_over_max || view.shows_notes();
+    if notes_loaded {
+        scraper.load_notes(&mut items);
+    }
+    // Checking the budget reads the lowest price of every item with a maximum price.
+    let budget_shown = !view.hides("Budget");
+    if budget_shown {
+        scraper.check_budget(&mut items);
+    }
+    let links: Vec<String> = items.iter().map(|i| i.sellers_link.clone()).collect();
+    let mut table: Vec<Vec<String>> = items.iter().map(WantlistItem::row).collect();
     let mut print_table = true;
-    if links.len() == 0 {
+    let mut wantlist_view = view.clone();
+    if links.is_empty() {
         println!("No items in your wantlist");
         std::process::exit(0);
     }
-    for row in table.iter_mut() {
-        row.insert(0, String::from(" "));
+    for (row, item) in table.iter_mut().zip(&items) {
+        row.insert(0, seen_marker(item, false));
     }
     loop {
         if print_table {
-            cli::print_table(WANTIST_HEADER, &table, "Releases", TableType::Default);
+            cli::print_view(
+                WANTIST_HEADER,
+                &table,
+                "Releases",
+                TableType::Default,
+                &wantlist_view,
+            );
         }
-        let mut selected_index: usize;
-        match cli::select_operation() {
-            MenuOptions::SelectId => selected_index = cli::ask_id(links.len(), "Select an Id:"),
-            MenuOptions::Exit => std::process::exit(0),
-            MenuOptions::GoBack => break,
+        let selected_index = match cli::select_wantlist_operation() {
+            WantlistMenu::Select => {
+                match cli::select_row("Select a release:", WANTIST_HEADER, &table, &wantlist_view) {
+                    Some(index) => index,
+                    None => {
+                        print_table = false;
+                        continue;
+                    }
+                }
+            }
+            WantlistMenu::View => {
+                wantlist_view.edit(WANTIST_HEADER);
+                print_table = true;
+                continue;
+            }
+            WantlistMenu::Notes => {
+                let selected =
+                    cli::select_row("Select a release:", WANTIST_HEADER, &table, &wantlist_view);
+                if let Some(index) = selected {
+                    if !notes_loaded {
+                        scraper.load_notes(std::slice::from_mut(&mut items[index]));
+                    }
+                    edit_want(&scraper, &mut items[index]);
+                    if budget_shown {
+                        scraper.check_budget(std::slice::from_mut(&mut items[index]));
+                    }
+                    let seen = table[index][0].clone();
+                    table[index] = items[index].row();
+                    table[index].insert(0, seen);
+                }
+                print_table = true;
+                continue;
+            }
+            WantlistMenu::Details => {
+                let selected =
+                    cli::select_row("Select a release:", WANTIST_HEADER, &table, &wantlist_view);
+                match selected.and_then(|index| items[index].release_id) {
+                    Some(release_id) => release::show_release(&scraper, release_id),
+                    None => println!("No release selected"),
+                }
+                print_table = false;
+                continue;
+            }
+            WantlistMenu::Exit => std::process::exit(0),
+            WantlistMenu::GoBack => break,
         };
-        if links[selected_index as usize].len() == 0 {
+        if links[selected_index].is_empty() {
             println!("No sellers for the selected item. Retry:");
             print_table = false;
             continue;
         }
         print_table = true;
-        table[selected_index][0] = String::from("X");
-        let selected = &links[selected_index];
-        let table = scraper.get_sellers(selected);
+        table[selected_index][0] = seen_marker(&items[selected_index], true);
+        if !notes_loaded {
+            scraper.load_notes(std::slice::from_mut(&mut items[selected_index]));
+        }
+        browse_sellers(
+            &scraper,
+            &items[selected_index],
+            filter,
+            &view,
+            hide_over_max,
+        );
+    }
+}
+
+/// Prints the sellers of a wantlist item, then the items of the selected seller to add to the
+/// cart.
+fn browse_sellers(
+    scraper: &web::DiscogsScraper,
+    item: &WantlistItem,
+    filter: ConditionFilter,
+    view: &TableView,
+    hide_over_max: bool,
+) {
+    let selected = &item.sellers_link;
+    let stats = web::release_id_from_link(selected).map(|id| scraper.get_price_stats(id));
+    let max = item.max_price();
+    let table = web::or_exit(scraper.get_sellers(
+        selected,
+        &filter,
+        stats.as_ref(),
+        max.as_ref(),
+        hide_over_max,
+    ));
+    let sellers_header = cli::sellers_header(scraper.converter().is_some());
+    let items_header = cli::items_header(scraper.converter().is_some());
+    let mut sellers_view = view.clone();
+    loop {
+        if let Some(stats) = &stats {
+            cli::print_price_stats(stats);
+        }
+        cli::print_view(
+            sellers_header,
+            &table,
+            "Sellers",
+            TableType::Default,
+            &sellers_view,
+        );
+        let selected_index = match cli::select_table_operation() {
+            MenuOptions::Select => {
+                match cli::select_row("Select a seller:", sellers_header, &table, &sellers_view) {
+                    Some(index) => index,
+                    None => continue,
+                }
+            }
+            MenuOptions::View => {
+                sellers_view.edit(sellers_header);
+                continue;
+            }
+            MenuOptions::Exit => std::process::exit(0),
+            MenuOptions::GoBack => return,
+        };
+        let selected = &table[selected_index][0];
+        let (links, table) = web::or_exit(scraper.get_seller_items(selected, &filter));
+        let mut items_view = view.clone();
+        let mut print_items = true;
         loop {
-            cli::print_table(SELLERS_HEADER, &table, "Sellers", TableType::Default);
-            match cli::select_operation() {
-                MenuOptions::SelectId => selected_index = cli::ask_id(table.len(), "Select an Id:"),
+            if print_items {
+                cli::print_view(
+                    items_header,
+                    &table,
+                    &format!("{} Items", selected),
+                    TableType::Default,
+                    &items_view,
+                );
+            }
+            print_items = false;
+            let selected = match cli::select_table_operation() {
+                MenuOptions::Select => {
+                    cli::select_rows("Select items to add:", items_header, &table, &items_view)
+                }
+                MenuOptions::View => {
+                    items_view.edit(items_header);
+                    print_items = true;
+                    continue;
+                }
                 MenuOptions::Exit => std::process::exit(0),
                 MenuOptions::GoBack => break,
             };
-            let selected = &table[selected_index][0];
-            let (links, table) = scraper.get_seller_items(selected);
-            cli::print_table(
-                ITEMS_HEADER,
-                &table,
-                &format!("{} Items", selected),
-                TableType::Default,
-            );
-            loop {
-                match cli::select_operation() {
-                    MenuOptions::SelectId => {
-                        selected_index = cli::ask_id(links.len(), "Select an Id:")
-                    }
-                    MenuOptions::Exit => std::process::exit(0),
-                    MenuOptions::GoBack => break,
-                };
-                scraper.add_to_cart(&links[selected_index as usize]);
+            for index in selected {
+                print_cart_outcome(&scraper.add_to_cart(&links[index]));
             }
         }
     }
 }
 
+/// Shows a random wantlist item passing the filters, or goes straight to its sellers.
+fn random_want(
+    scraper: web::DiscogsScraper,
+    random: web::RandomFilter,
+    sellers: bool,
+    filter: ConditionFilter,
+    view: TableView,
+    hide_over_max: bool,
+) {
+    let Some(item) = scraper.random_wantlist(&random) else {
+        println!("No wantlist item matches the filters");
+        std::process::exit(0);
+    };
+    if !sellers {
+        return check_wantlist(scraper, vec![item], filter, view, hide_over_max);
+    }
+    match item.sellers_link.is_empty() {
+        true => println!("No sellers for {}", item.title),
+        false => browse_sellers(&scraper, &item, filter, &view, hide_over_max),
+    }
+}
+
+fn edit_want(scraper: &web::DiscogsScraper, item: &mut WantlistItem) {
+    let Some(release_id) = item.release_id else {
+        println!("Unable to find the release of {}", item.title);
+        return;
+    };
+    let notes = cli::edit_text("Notes:", &item.notes);
+    let rating = cli::select_rating(item.rating);
+    if scraper.edit_want(release_id, Some(&notes), Some(rating)) {
+        item.notes = notes;
+        item.rating = rating;
+        println!("Notes saved");
+    } else {
+        println!("Unable to save the notes of {}", item.title);
+    }
+}
+
+fn wantlist_notes(
+    scraper: web::DiscogsScraper,
+    release_id: u64,
+    notes: Option<String>,
+    rating: Option<u8>,
+) {
+    if (notes.is_some() || rating.is_some())
+        && !scraper.edit_want(release_id, notes.as_deref(), rating)
+    {
+        println!("Unable to save the notes of release {}", release_id);
+        std::process::exit(1);
+    }
+    let wants = scraper.wants();
+    let Some(want) = wants.get(&release_id) else {
+        println!("Release {} is not in your wantlist", release_id);
+        std::process::exit(1);
+    };
+    let rating = match want.rating {
+        0 => String::new(),
+        rating => format!("{}/5", rating),
+    };
+    let row = vec![release_id.to_string(), rating, want.notes.clone()];
+    cli::print_table(NOTES_HEADER, &[row], "Wantlist Notes", TableType::Info);
+}
+
 fn master_release_to_wantlist(
     scraper: web::DiscogsScraper,
     search: &str,
     operation: WantlistOperations,
 ) {
-    let (links, table) = scraper.search_release(&search);
-    cli::print_table(
-        RELEASE_HEADER,
-        &table,
-        "Master Releases",
-        TableType::Default,
+    let link = &cli::select_master(&scraper, search);
+    match operation {
+        WantlistOperations::Add => scraper.add_lps_to_wantlist(link),
+        WantlistOperations::Remove => scraper.remove_all_wantlist(link),
+    }
+}
+
+fn remove_item(scraper: &web::DiscogsScraper, order: &CartOrder, item: &CartItem) {
+    match scraper.remove_from_cart(order, item) {
+        true => println!("Removed {}", item.name),
+        false => println!("Unable to remove {} from the cart", item.name),
+    }
+}
+
+fn empty_order(scraper: &web::DiscogsScraper, order: &CartOrder) {
+    let removed = scraper.empty_order(order);
+    println!(
+        "Removed {} of {} items from {}",
+        removed,
+        order.items.len(),
+        order.seller
     );
-    let selected_index: usize;
-    match cli::select_operation() {
-        MenuOptions::SelectId => selected_index = cli::ask_id(links.len(), "Select an Id:"),
-        _ => std::process::exit(0),
+}
+
+fn change_shipping(scraper: &web::DiscogsScraper, order: &CartOrder, index: usize) {
+    let option = &order.shipping[index];
+    match scraper.set_shipping(order, option) {
+        true => println!(
+            "Shipping for {} set to {}",
+            order.seller, option.description
+        ),
+        false => println!("Unable to change the shipping of {}", order.seller),
+    }
+}
+
+/// Replaces a cart item with another seller's listing of the same release.
+fn move_item(
+    scraper: &web::DiscogsScraper,
+    order: &CartOrder,
+    item: &CartItem,
+    filter: &ConditionFilter,
+) {
+    let Some(release_id) = scraper.cart_item_release(item) else {
+        println!("Can't find the release of {}", item.name);
+        return;
     };
-    let link = &links[selected_index];
-    match operation {
-        WantlistOperations::Add => scraper.add_lps_to_wantlist(&link),
-        WantlistOperations::Remove => scraper.remove_all_wantlist(&link),
+    let listings: Vec<web::Listing> =
+        web::or_exit(scraper.get_listings(&format!("sell/release/{}", release_id), filter))
+            .into_iter()
+            .filter(|listing| listing.seller != order.seller)
+            .collect();
+    if listings.is_empty() {
+        println!("No other sellers for {}", item.name);
+        return;
+    }
+    let table: Vec<Vec<String>> = listings
+        .iter()
+        .map(|listing| {
+            vec![
+                listing.seller.clone(),
+                listing.shipping_from.clone(),
+                listing.grading.to_string(),
+                listing.price.clone(),
+            ]
+        })
+        .collect();
+    cli::print_table(MOVE_HEADER, &table, &item.name, TableType::Default);
+    let selected_index = match cli::select_operation() {
+        MenuOptions::Select => {
+            match cli::select_row(
+                "Select a seller:",
+                MOVE_HEADER,
+                &table,
+                &TableView::default(),
+            ) {
+                Some(index) => index,
+                None => return,
+            }
+        }
+        MenuOptions::Exit => std::process::exit(0),
+        MenuOptions::GoBack | MenuOptions::View => return,
+    };
+    let listing = &listings[selected_index];
+    let outcome = scraper.add_to_cart(&listing.add_to_cart_link());
+    print_cart_outcome(&outcome);
+    // Keep the current item unless its replacement is really in the cart
+    if outcome.is_added() {
+        remove_item(scraper, order, item);
+        println!("Moved {} to {}", item.name, listing.seller);
     }
 }
 
-fn get_cart(scraper: web::DiscogsScraper) {
-    let (sellers, tables) = scraper.get_cart();
-    for (seller, table) in zip(sellers, tables) {
-        cli::print_table(CART_HEADER, &table, &seller, TableType::Cart);
+fn find_order<'a>(orders: &'a [CartOrder], seller: &str) -> &'a CartOrder {
+    match orders
+        .iter()
+        .find(|o| o.seller.eq_ignore_ascii_case(seller))
+    {
+        Some(order) => order,
+        None => {
+            println!("No order from {} in your cart", seller);
+            std::process::exit(1);
+        }
     }
 }
 
+fn find_item<'a>(orders: &'a [CartOrder], listing_id: &str) -> (&'a CartOrder, &'a CartItem) {
+    let found = orders.iter().find_map(|order| {
+        order
+            .items
+            .iter()
+            .find(|item| item.id == listing_id)
+            .map(|item| (order, item))
+    });
+    match found {
+        Some(found) => found,
+        None => {
+            println!("Listing {} is not in your cart", listing_id);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn select_order(orders: &[CartOrder]) -> Option<&CartOrder> {
+    let titles = orders.iter().map(CartOrder::title).collect();
+    cli::select_index("Select a seller:", titles).map(|i| &orders[i])
+}
+
+fn select_item(orders: &[CartOrder]) -> Option<(&CartOrder, &CartItem)> {
+    let items: Vec<(&CartOrder, &CartItem)> = orders
+        .iter()
+        .flat_map(|order| order.items.iter().map(move |item| (order, item)))
+        .collect();
+    let labels = items
+        .iter()
+        .map(|(order, item)| format!("{} | {} | {}", item.name, order.seller, item.price))
+        .collect();
+    cli::select_index("Select an item:", labels).map(|i| items[i])
+}
+
+fn cart_command(scraper: web::DiscogsScraper, command: CartCommands, filter: ConditionFilter) {
+    let orders = scraper.get_cart();
+    match command {
+        CartCommands::Remove { listing_id } => {
+            let (order, item) = find_item(&orders, &listing_id);
+            remove_item(&scraper, order, item);
+        }
+        CartCommands::Empty { seller } => empty_order(&scraper, find_order(&orders, &seller)),
+        CartCommands::Shipping { seller, method } => {
+            let order = find_order(&orders, &seller);
+            let index = match method.parse::<usize>() {
+                Ok(index) if index < order.shipping.len() => Some(index),
+                _ => order.shipping.iter().position(|option| {
+                    option
+                        .description
+                        .to_lowercase()
+                        .contains(&method.to_lowercase())
+                }),
+            };
+            match index {
+                Some(index) => change_shipping(&scraper, order, index),
+                None => println!("No shipping method of {} matches {}", seller, method),
+            }
+        }
+        CartCommands::Move { listing_id } => {
+            let (order, item) = find_item(&orders, &listing_id);
+            move_item(&scraper, order, item, &filter);
+        }
+    }
+}
+
+fn get_cart(scraper: web::DiscogsScraper, filter: ConditionFilter) {
+    loop {
+        let orders = scraper.get_cart();
+        if orders.is_empty() {
+            println!("Your cart is empty");
+            return;
+        }
+        for order in orders.iter() {
+            cli::print_table(
+                CART_HEADER,
+                &order.table(&filter),
+                &order.title(),
+                TableType::Cart,
+            );
+        }
+        cli::print_table(
+            CART_TOTAL_HEADER,
+            &web::cart_totals(&orders, scraper.converter()),
+            "Cart Total",
+            TableType::Info,
+        );
+        match cli::select_cart_action() {
+            CartMenu::RemoveItem => {
+                if let Some((order, item)) = select_item(&orders) {
+                    remove_item(&scraper, order, item);
+                }
+            }
+            CartMenu::EmptyOrder => {
+                if let Some(order) = select_order(&orders) {
+                    empty_order(&scraper, order);
+                }
+            }
+            CartMenu::ChangeShipping => {
+                if let Some(order) = select_order(&orders) {
+                    let options = order
+                        .shipping
+                        .iter()
+                        .map(|option| option.description.clone())
+                        .collect();
+                    if let Some(index) = cli::select_index("Select shipping:", options) {
+                        change_shipping(&scraper, order, index);
+                    }
+                }
+            }
+            CartMenu::MoveItem => {
+                if let Some((order, item)) = select_item(&orders) {
+                    move_item(&scraper, order, item, &filter);
+                }
+            }
+            CartMenu::Exit => return,
+        }
+    }
+}
+
+fn price_history(scraper: web::DiscogsScraper, release_id: u64, view: &TableView) {
+    let Some(db) = scraper.database() else {
+        println!("Price history needs a database, pass it with --db.");
+        std::process::exit(1);
+    };
+    let table: Vec<Vec<String>> = db
+        .price_history(release_id)
+        .into_iter()
+        .map(|trend| {
+            vec![
+                trend.day,
+                trend.currency,
+                trend.listings.to_string(),
+                format!("{:.2}", trend.lowest),
+                format!("{:.2}", trend.average),
+                format!("{:.2}", trend.highest),
+            ]
+        })
+        .collect();
+    if table.is_empty() {
+        println!("No listings recorded for release {}", release_id);
+        return;
+    }
+    cli::print_view(
+        HISTORY_HEADER,
+        &table,
+        &format!("Release {}", release_id),
+        TableType::Default,
+        view,
+    );
+}
+
 fn main() {
     let args = cli::Args::parse();
-    let cookies_path = args.cookies;
-    let scraper = web::DiscogsScraper::new(&cookies_path);
+    args.configure_output();
+    let filter = args.condition_filter();
+    let view = args.table_view();
+    let mut scraper = web::DiscogsScraper::new(&args.cookies);
+    if let Some(path) = &args.db {
+        scraper = scraper.with_database(db::Database::open(path, args.cache_ttl * 60));
+    }
+    if let Some(currency) = &args.currency {
+        scraper = scraper.with_converter(rates::Converter::open(
+            &args.rates,
+            currency,
+            args.refresh_rates,
+            &rates::Frankfurter,
+        ));
+    }
     match args.command {
-        Wantlist { query } => check_wantlist(scraper, query),
+        Wantlist { query, release } => {
+            let items = match release {
+                Some(release_id) => scraper.wantlist_release(release_id),
+                None => scraper.get_wantlist(query),
+            };
+            check_wantlist(scraper, items, filter, view, args.hide_over_max)
+        }
+        Random {
+            with_sellers,
+            format,
+            genre,
+            under,
+            sellers,
+        } => {
+            let random = web::RandomFilter {
+                with_sellers,
+                format,
+                genre,
+                under,
+            };
+            random_want(scraper, random, sellers, filter, view, args.hide_over_max)
+        }
         Add { release } => master_release_to_wantlist(scraper, &release, WantlistOperations::Add),
         Remove { release } => {
             master_release_to_wantlist(scraper, &release, WantlistOperations::Remove)
         }
-        Cart => get_cart(scraper),
+        Cart { action: None } => get_cart(scraper, filter),
+        Cart {
+            action: Some(command),
+        } => cart_command(scraper, command, filter),
+        Tui { query } => tui::run_tui(&scraper, query, filter, &view),
+        Inventory { action } => inventory::inventory_command(scraper, action, &view),
+        Search {
+            query,
+            kind,
+            genre,
+            style,
+            format,
+            country,
+            year,
+            label,
+            pages,
+        } => {
+            let query = web::SearchQuery {
+                query,
+                kind: kind.as_str().to_string(),
+                genre,
+                style,
+                format,
+                country,
+                year,
+                label,
+                ..Default::default()
+            };
+            search::search_command(scraper, query, pages, filter, view)
+        }
+        Lookup {
+            code,
+            barcode,
+            catno,
+        } => search::lookup_command(scraper, &code, barcode, catno, filter, view),
+        Discography {
+            kind,
+            name,
+            id,
+            format,
+            from,
+            to,
+            role,
+        } => {
+            let filter = discography::DiscographyFilter {
+                format,
+                from,
+                to,
+                roles: role,
+            };
+            discography::discography_command(scraper, kind, &name, id, filter, &view)
+        }
+        Versions { master, id } => {
+            versions::versions_command(&scraper, &master, id, &filter, &view)
+        }
+        Release { release_id } => release::show_release(&scraper, release_id),
+        Orders { action } => orders::orders_command(scraper, action, &view),
+        Notes {
+            release_id,
+            set,
+            rating,
+        } => wantlist_notes(scraper, release_id, set, rating),
+        Collection { action } => collection::collection_command(scraper, action, &view),
+        History { release_id } => price_history(scraper, release_id, &view),
+        Watch {
+            query,
+            max_price,
+            item_max,
+            state,
+            interval,
+        } => {
+            let options = watch::WatchOptions {
+                query,
+                max_price,
+                item_max: item_max.into_iter().collect(),
+                state,
+                interval,
+            };
+            // Each scan has to see the current listings, not pages cached by the previous one.
+            watch::watch(&scraper.without_page_cache(), &options, &filter)
+        }
     }
 }
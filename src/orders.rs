@@ -0,0 +1,77 @@
+use crate::cli::{self, OrderCommands, TableType, TableView};
+use crate::web::{DiscogsScraper, Price};
+
+const ORDERS_HEADER: &[&str] = &[
+    "Order", "Date", "Seller", "Status", "Items", "Total", "Tracking",
+];
+const ORDER_HEADER: &[&str] = &["Status", "Seller", "Created", "Shipping", "Total"];
+const ORDER_ITEMS_HEADER: &[&str] = &["Release", "Description", "Price"];
+const MESSAGES_HEADER: &[&str] = &["Date", "From", "Message"];
+
+fn list_orders(scraper: &DiscogsScraper, view: &TableView) {
+    let (ids, table) = scraper.get_purchases();
+    if ids.is_empty() {
+        println!("You have no orders");
+        return;
+    }
+    cli::print_view(ORDERS_HEADER, &table, "Orders", TableType::Default, view);
+}
+
+fn show_order(scraper: &DiscogsScraper, order_id: &str) {
+    let Some(order) = scraper.get_order(order_id) else {
+        println!("Unable to read order {}", order_id);
+        std::process::exit(1);
+    };
+    let show = |price: &Option<Price>| price.as_ref().map_or(String::from("-"), Price::to_string);
+    let row = vec![
+        order.status.clone(),
+        order.seller.clone(),
+        order.created.clone(),
+        show(&order.shipping),
+        show(&order.total),
+    ];
+    cli::print_table(
+        ORDER_HEADER,
+        &[row],
+        &format!("Order {}", order.id),
+        TableType::Info,
+    );
+    let items: Vec<Vec<String>> = order
+        .items
+        .iter()
+        .map(|item| {
+            vec![
+                item.release_id.to_string(),
+                item.description.clone(),
+                show(&item.price),
+            ]
+        })
+        .collect();
+    cli::print_table(ORDER_ITEMS_HEADER, &items, "Items", TableType::Default);
+    let messages: Vec<Vec<String>> = order
+        .messages
+        .into_iter()
+        .map(|message| vec![message.timestamp, message.from, message.message])
+        .collect();
+    if messages.is_empty() {
+        println!("No messages");
+    } else {
+        cli::print_table(MESSAGES_HEADER, &messages, "Messages", TableType::Default);
+    }
+}
+
+pub fn orders_command(scraper: DiscogsScraper, command: Option<OrderCommands>, view: &TableView) {
+    match command {
+        None => list_orders(&scraper, view),
+        Some(OrderCommands::Show { order_id }) => show_order(&scraper, &order_id),
+        Some(OrderCommands::Message { order_id, message }) => {
+            match scraper.send_order_message(&order_id, &message) {
+                true => println!("Message sent"),
+                false => {
+                    println!("Unable to send the message to order {}", order_id);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+}
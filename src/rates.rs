@@ -0,0 +1,105 @@
+use crate::web::Price;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const FRANKFURTER_URL: &str = "https://api.frankfurter.app/latest";
+
+/// Exchange rates relative to `base`: one unit of `base` is worth `rates[code]` of `code`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExchangeRates {
+    pub base: String,
+    #[serde(default)]
+    pub date: Option<String>,
+    pub rates: HashMap<String, f32>,
+}
+
+impl ExchangeRates {
+    pub fn load(path: &str) -> Option<ExchangeRates> {
+        let data = std::fs::read_to_string(path).ok()?;
+        Some(serde_json::from_str(&data).expect("Unable to parse exchange rates file."))
+    }
+
+    pub fn save(&self, path: &str) {
+        let data = serde_json::to_string_pretty(self).unwrap();
+        std::fs::write(path, data).expect("Unable to write exchange rates file.");
+    }
+
+    fn rate(&self, currency: &str) -> Option<f32> {
+        match currency == self.base {
+            true => Some(1.0),
+            false => self.rates.get(currency).copied(),
+        }
+    }
+
+    /// `price` in `currency`, if the rates of both currencies are known.
+    pub fn convert(&self, price: &Price, currency: &str) -> Option<Price> {
+        if price.currency == currency {
+            return Some(price.clone());
+        }
+        let value = price.value / self.rate(&price.currency)? * self.rate(currency)?;
+        Some(Price {
+            value,
+            currency: currency.to_string(),
+        })
+    }
+}
+
+/// Source of up-to-date exchange rates.
+pub trait RateProvider {
+    fn latest(&self, base: &str) -> ExchangeRates;
+}
+
+/// Rates published by the European Central Bank, through the Frankfurter API.
+pub struct Frankfurter;
+
+impl RateProvider for Frankfurter {
+    fn latest(&self, base: &str) -> ExchangeRates {
+        reqwest::blocking::Client::new()
+            .get(FRANKFURTER_URL)
+            .query(&[("from", base)])
+            .send()
+            .expect("Failed to fetch exchange rates.")
+            .json()
+            .expect("Unable to parse exchange rates.")
+    }
+}
+
+/// Converts prices to the currency chosen by the user.
+#[derive(Debug, Clone)]
+pub struct Converter {
+    pub rates: ExchangeRates,
+    pub currency: String,
+}
+
+impl Converter {
+    /// Reads the rates stored at `path`, fetching them from `provider` (and storing them) when
+    /// the file doesn't exist yet or `refresh` is set.
+    pub fn open(
+        path: &str,
+        currency: &str,
+        refresh: bool,
+        provider: &dyn RateProvider,
+    ) -> Converter {
+        let currency = currency.to_uppercase();
+        let rates = match ExchangeRates::load(path) {
+            Some(rates) if !refresh => rates,
+            _ => {
+                let rates = provider.latest(&currency);
+                rates.save(path);
+                rates
+            }
+        };
+        Converter { rates, currency }
+    }
+
+    pub fn convert(&self, price: &Price) -> Option<Price> {
+        self.rates.convert(price, &self.currency)
+    }
+
+    /// Converted price shown next to an asking price such as "€25.00" or "about $27.10".
+    pub fn convert_text(&self, text: &str) -> String {
+        Price::parse(text)
+            .and_then(|price| self.convert(&price))
+            .map_or(String::new(), |price| price.to_string())
+    }
+}
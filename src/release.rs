@@ -0,0 +1,140 @@
+use crate::cli::{self, TableType, TableView};
+use crate::web::{self, ConditionFilter, DiscogsScraper};
+use itertools::Itertools;
+
+const RELEASE_HEADER: &[&str] = &["Artists", "Title", "Year", "Country", "Format", "Genres"];
+const LABELS_HEADER: &[&str] = &["Label", "Catalog#"];
+const TRACKLIST_HEADER: &[&str] = &["Position", "Title", "Duration", "Credits"];
+const IDENTIFIERS_HEADER: &[&str] = &["Type", "Value", "Description"];
+const CREDITS_HEADER: &[&str] = &["Name", "Role", "Tracks"];
+const COMMUNITY_HEADER: &[&str] = &["Have", "Want", "Rating", "Votes"];
+const IMAGES_HEADER: &[&str] = &["Type", "URL"];
+
+fn print_section(header: &'static [&str], table: Vec<Vec<String>>, title: &str) {
+    if !table.is_empty() {
+        cli::print_table(header, &table, title, TableType::Info);
+    }
+}
+
+/// Prints everything Discogs knows about a release.
+pub fn show_release(scraper: &DiscogsScraper, release_id: u64) {
+    let release = scraper.get_release_details(release_id);
+    let genres = release
+        .genres
+        .iter()
+        .chain(release.styles.iter())
+        .join(", ");
+    let row = vec![
+        release.get_artists(),
+        release.title.clone(),
+        match release.year {
+            0 => String::new(),
+            year => year.to_string(),
+        },
+        release.country.clone().unwrap_or_default(),
+        release.get_formats(),
+        genres,
+    ];
+    cli::print_table(
+        RELEASE_HEADER,
+        &[row],
+        &format!("Release {}", release_id),
+        TableType::Info,
+    );
+    let labels = release
+        .labels
+        .iter()
+        .map(|label| vec![label.name.clone(), label.catno.clone()])
+        .collect();
+    print_section(LABELS_HEADER, labels, "Labels");
+    let tracklist = release
+        .tracklist
+        .iter()
+        .map(|track| {
+            let credits = track
+                .extraartists
+                .iter()
+                .map(|credit| format!("{}: {}", credit.role, credit.name))
+                .join("\n");
+            vec![
+                track.position.clone(),
+                track.title.clone(),
+                track.duration.clone(),
+                credits,
+            ]
+        })
+        .collect();
+    print_section(TRACKLIST_HEADER, tracklist, "Tracklist");
+    let identifiers = release
+        .identifiers
+        .iter()
+        .map(|identifier| {
+            vec![
+                identifier.kind.clone(),
+                identifier.value.clone(),
+                identifier.description.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+    print_section(IDENTIFIERS_HEADER, identifiers, "Identifiers");
+    let credits = release
+        .extraartists
+        .iter()
+        .map(|credit| {
+            vec![
+                credit.name.clone(),
+                credit.role.clone(),
+                credit.tracks.clone(),
+            ]
+        })
+        .collect();
+    print_section(CREDITS_HEADER, credits, "Credits");
+    if let Some(community) = &release.community {
+        let row = vec![
+            community.have.to_string(),
+            community.want.to_string(),
+            format!("{:.2}", community.rating.average),
+            community.rating.count.to_string(),
+        ];
+        print_section(COMMUNITY_HEADER, vec![row], "Community");
+    }
+    if let Some(notes) = release
+        .notes
+        .as_ref()
+        .filter(|notes| !notes.trim().is_empty())
+    {
+        print_section(&["Notes"], vec![vec![notes.trim().to_string()]], "Notes");
+    }
+    let images = release
+        .images
+        .iter()
+        .map(|image| vec![image.kind.clone(), image.uri.clone()])
+        .collect();
+    print_section(IMAGES_HEADER, images, "Images");
+}
+
+/// Prints the price statistics and the marketplace listings of a release.
+pub fn show_listings(
+    scraper: &DiscogsScraper,
+    release_id: u64,
+    title: &str,
+    filter: &ConditionFilter,
+    view: &TableView,
+) {
+    let stats = scraper.get_price_stats(release_id);
+    cli::print_price_stats(&stats);
+    let sellers = web::or_exit(scraper.get_sellers(
+        &format!("sell/release/{}", release_id),
+        filter,
+        Some(&stats),
+        None,
+        false,
+    ));
+    cli::print_view(
+        cli::sellers_header(scraper.converter().is_some()),
+        &sellers,
+        title,
+        TableType::Default,
+        view,
+    );
+}
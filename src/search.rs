@@ -0,0 +1,170 @@
+use crate::cli::{self, MenuOptions, SearchAction, TableType, TableView};
+use crate::discography::{self, DiscographyFilter};
+use crate::web::{ConditionFilter, DiscogsScraper, SearchQuery, SearchResult, WEB_HOME_URL};
+use crate::{release, versions};
+
+const SEARCH_HEADER: &[&str] = &[
+    "Type", "Title", "Format", "Label", "Catalog#", "Country", "Year", "Have", "Want",
+];
+const LOOKUP_HEADER: &[&str] = &[
+    "Title",
+    "Format",
+    "Label",
+    "Catalog#",
+    "Country",
+    "Year",
+    "Wantlist",
+    "Collection",
+    "Lowest",
+];
+/// Results of a lookup whose lowest price is shown.
+const LOOKUP_PRICES: usize = 10;
+
+/// Acts on a search result according to its type, until the user goes back to the results.
+fn result_actions(
+    scraper: &DiscogsScraper,
+    result: &SearchResult,
+    filter: &ConditionFilter,
+    view: &TableView,
+) {
+    loop {
+        match cli::select_search_action(&result.kind) {
+            SearchAction::AddToWantlist => {
+                match scraper.add_releases_to_wantlist(vec![result.id as i64]) {
+                    Ok(added) => println!("Added {} items to wantlist.", added),
+                    Err(messages) => println!("{:#?}", messages),
+                }
+            }
+            SearchAction::AddLps => scraper.add_lps_to_wantlist(&result.uri),
+            SearchAction::Listings => {
+                release::show_listings(scraper, result.id, &result.title, filter, view)
+            }
+            SearchAction::Details => release::show_release(scraper, result.id),
+            SearchAction::Versions => versions::browse_versions(scraper, result.id, filter, view),
+            SearchAction::Discography => discography::discography_to_wantlist(
+                scraper,
+                &result.kind,
+                result.id,
+                &DiscographyFilter::default(),
+                view,
+            ),
+            SearchAction::Link => println!("{}{}", WEB_HOME_URL, result.uri),
+            SearchAction::GoBack => return,
+        }
+    }
+}
+
+/// Prints the results and acts on the selected ones until the user goes back.
+fn browse_results(
+    scraper: &DiscogsScraper,
+    results: &[SearchResult],
+    header: &[&str],
+    table: &[Vec<String>],
+    title: &str,
+    filter: &ConditionFilter,
+    view: &TableView,
+) {
+    let mut results_view = view.clone();
+    let mut print_results = true;
+    loop {
+        if print_results {
+            cli::print_view(header, table, title, TableType::Default, &results_view);
+        }
+        print_results = false;
+        match cli::select_table_operation() {
+            MenuOptions::Select => {
+                let selected = cli::select_row("Select a result:", header, table, &results_view);
+                if let Some(index) = selected {
+                    result_actions(scraper, &results[index], filter, view);
+                }
+            }
+            MenuOptions::View => {
+                results_view.edit(header);
+                print_results = true;
+            }
+            MenuOptions::GoBack | MenuOptions::Exit => return,
+        }
+    }
+}
+
+pub fn search_command(
+    scraper: DiscogsScraper,
+    query: SearchQuery,
+    pages: usize,
+    filter: ConditionFilter,
+    view: TableView,
+) {
+    let results = scraper.search_database(&query, pages);
+    if results.is_empty() {
+        println!("No results found");
+        return;
+    }
+    let table: Vec<Vec<String>> = results.iter().map(SearchResult::row).collect();
+    browse_results(
+        &scraper,
+        &results,
+        SEARCH_HEADER,
+        &table,
+        "Search Results",
+        &filter,
+        &view,
+    );
+}
+
+fn looks_like_barcode(code: &str) -> bool {
+    let digits: Vec<char> = code
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect();
+    digits.len() >= 8 && digits.iter().all(char::is_ascii_digit)
+}
+
+/// Releases matching a barcode or catalog number, marked when they are in the wantlist or the
+/// collection and shown with their lowest marketplace price.
+pub fn lookup_command(
+    scraper: DiscogsScraper,
+    code: &str,
+    barcode: bool,
+    catno: bool,
+    filter: ConditionFilter,
+    view: TableView,
+) {
+    let results = match (barcode, catno) {
+        (true, _) => scraper.search_identifier(code, true),
+        (_, true) => scraper.search_identifier(code, false),
+        _ if looks_like_barcode(code) => {
+            let results = scraper.search_identifier(code, true);
+            match results.is_empty() {
+                true => scraper.search_identifier(code, false),
+                false => results,
+            }
+        }
+        _ => scraper.search_identifier(code, false),
+    };
+    if results.is_empty() {
+        println!("No release found for {}", code);
+        return;
+    }
+    // Prices are looked up concurrently, and only for the first results to stay within the API
+    // rate limit; a code rarely matches more releases.
+    let currency = scraper.converter().map_or("", |c| c.currency.as_str());
+    let releases: Vec<(u64, String)> = results
+        .iter()
+        .take(LOOKUP_PRICES)
+        .map(|result| (result.id, currency.to_string()))
+        .collect();
+    let lowest = scraper.lowest_prices(&releases);
+    let table: Vec<Vec<String>> = results
+        .iter()
+        .map(|result| result.lookup_row(lowest.get(&result.id)))
+        .collect();
+    browse_results(
+        &scraper,
+        &results,
+        LOOKUP_HEADER,
+        &table,
+        code,
+        &filter,
+        &view,
+    );
+}
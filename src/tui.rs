@@ -0,0 +1,473 @@
+use crate::cli::{compare_cells, items_header, sellers_header, TableView};
+use crate::web::{CartOrder, ConditionFilter, DiscogsScraper, WantlistItem};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState};
+use std::io::{self, Stdout};
+
+const WANTLIST_HEADER: &[&str] = &[
+    "Sellers", "Title", "Format", "Year", "Notes", "Max", "Budget",
+];
+const CART_HEADER: &[&str] = &["Seller", "Item", "Condition", "Price"];
+const HELP: &str = "↑/↓ move  Enter open  Esc back  / search  s sort  r reverse  a add to cart  c cart  d remove  q quit";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum View {
+    Wantlist,
+    Sellers,
+    Items,
+    Cart,
+}
+
+/// A table of the navigation stack, with the link each row leads to.
+struct Pane {
+    view: View,
+    title: String,
+    header: &'static [&'static str],
+    widths: &'static [u16],
+    rows: Vec<Vec<String>>,
+    links: Vec<String>,
+    /// Indices of `rows` matching the search, in display order.
+    visible: Vec<usize>,
+    state: TableState,
+    search: String,
+    sort: Option<(usize, bool)>,
+}
+
+impl Pane {
+    fn new(
+        view: View,
+        title: String,
+        header: &'static [&'static str],
+        widths: &'static [u16],
+        links: Vec<String>,
+        rows: Vec<Vec<String>>,
+    ) -> Pane {
+        let mut pane = Pane {
+            view,
+            title,
+            header,
+            widths,
+            rows,
+            links,
+            visible: Vec::new(),
+            state: TableState::default(),
+            search: String::new(),
+            sort: None,
+        };
+        pane.refresh();
+        pane
+    }
+
+    /// Recomputes the visible rows after the search or the sort changed.
+    fn refresh(&mut self) {
+        let search = self.search.to_lowercase();
+        self.visible = (0..self.rows.len())
+            .filter(|&i| {
+                search.is_empty()
+                    || self.rows[i]
+                        .iter()
+                        .any(|cell| cell.to_lowercase().contains(&search))
+            })
+            .collect();
+        if let Some((column, descending)) = self.sort {
+            let rows = &self.rows;
+            self.visible.sort_by(|&a, &b| {
+                let ordering =
+                    compare_cells(self.header[column], &rows[a][column], &rows[b][column]);
+                match descending {
+                    true => ordering.reverse(),
+                    false => ordering,
+                }
+            });
+        }
+        let selected = match self.visible.is_empty() {
+            true => None,
+            false => Some(
+                self.state
+                    .selected()
+                    .unwrap_or(0)
+                    .min(self.visible.len() - 1),
+            ),
+        };
+        self.state.select(selected);
+    }
+
+    fn selected(&self) -> Option<usize> {
+        self.state.selected().map(|i| self.visible[i])
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        if self.visible.is_empty() {
+            return;
+        }
+        let current = self.state.selected().unwrap_or(0) as isize;
+        let next = (current + delta).clamp(0, self.visible.len() as isize - 1);
+        self.state.select(Some(next as usize));
+    }
+
+    fn cycle_sort(&mut self) {
+        self.sort = match self.sort {
+            None => Some((0, false)),
+            Some((column, _)) if column + 1 < self.header.len() => Some((column + 1, false)),
+            Some(_) => None,
+        };
+        self.refresh();
+    }
+
+    fn reverse_sort(&mut self) {
+        if let Some((column, descending)) = self.sort {
+            self.sort = Some((column, !descending));
+            self.refresh();
+        }
+    }
+}
+
+struct App<'a> {
+    scraper: &'a DiscogsScraper,
+    filter: ConditionFilter,
+    /// The wantlist rows, by the index in the wantlist pane.
+    items: Vec<WantlistItem>,
+    stack: Vec<Pane>,
+    orders: Vec<CartOrder>,
+    searching: bool,
+    status: String,
+}
+
+impl App<'_> {
+    fn pane(&mut self) -> &mut Pane {
+        self.stack.last_mut().unwrap()
+    }
+
+    fn open_selected(&mut self) {
+        let pane = self.stack.last().unwrap();
+        let Some(index) = pane.selected() else {
+            return;
+        };
+        let converted = self.scraper.converter().is_some();
+        match pane.view {
+            View::Wantlist => {
+                let link = pane.links[index].clone();
+                if link.is_empty() {
+                    self.status = String::from("No sellers for the selected item.");
+                    return;
+                }
+                let title = pane.rows[index][1].clone();
+                // The maximum price is in the notes, which are only read up front when shown.
+                let item = &mut self.items[index];
+                self.scraper.load_notes(std::slice::from_mut(item));
+                let max = item.max_price();
+                let sellers =
+                    self.scraper
+                        .get_sellers(&link, &self.filter, None, max.as_ref(), false);
+                let rows = match sellers {
+                    Ok(rows) => rows,
+                    Err(e) => return self.show_error(e),
+                };
+                let sellers = rows.iter().map(|row| row[0].clone()).collect();
+                self.stack.push(Pane::new(
+                    View::Sellers,
+                    title,
+                    sellers_header(converted),
+                    match converted {
+                        true => &[16, 8, 7, 13, 16, 12, 12, 6],
+                        false => &[18, 9, 8, 15, 18, 14, 8],
+                    },
+                    sellers,
+                    rows,
+                ));
+            }
+            View::Sellers => {
+                let seller = pane.links[index].clone();
+                let (links, rows) = match self.scraper.get_seller_items(&seller, &self.filter) {
+                    Ok(items) => items,
+                    Err(e) => return self.show_error(e),
+                };
+                self.stack.push(Pane::new(
+                    View::Items,
+                    format!("{} Items", seller),
+                    items_header(converted),
+                    match converted {
+                        true => &[52, 16, 16, 16],
+                        false => &[60, 20, 20],
+                    },
+                    links,
+                    rows,
+                ));
+            }
+            View::Items => return self.add_selected(),
+            View::Cart => return,
+        }
+        self.status = self.scraper.take_warnings().join(" ");
+    }
+
+    fn show_error(&mut self, error: String) {
+        let mut status = vec![error];
+        status.extend(self.scraper.take_warnings());
+        self.status = status.join(" ");
+    }
+
+    fn add_selected(&mut self) {
+        let pane = self.stack.last().unwrap();
+        if pane.view != View::Items {
+            return;
+        }
+        if let Some(index) = pane.selected() {
+            self.status = self.scraper.add_to_cart(&pane.links[index]).to_string();
+        }
+    }
+
+    fn open_cart(&mut self) {
+        self.orders = self.scraper.get_cart();
+        let mut links = Vec::new();
+        let mut rows = Vec::new();
+        for (i, order) in self.orders.iter().enumerate() {
+            for (j, item) in order.items.iter().enumerate() {
+                links.push(format!("{}/{}", i, j));
+                rows.push(vec![
+                    order.seller.clone(),
+                    item.name.clone(),
+                    item.grading.to_string(),
+                    item.price.clone(),
+                ]);
+            }
+        }
+        if self.pane().view == View::Cart {
+            self.stack.pop();
+        }
+        self.stack.push(Pane::new(
+            View::Cart,
+            String::from("Cart"),
+            CART_HEADER,
+            &[20, 50, 15, 15],
+            links,
+            rows,
+        ));
+    }
+
+    fn remove_selected(&mut self) {
+        let pane = self.stack.last().unwrap();
+        if pane.view != View::Cart {
+            return;
+        }
+        let Some(index) = pane.selected() else {
+            return;
+        };
+        let (order, item) = pane.links[index].split_once('/').unwrap();
+        let order = &self.orders[order.parse::<usize>().unwrap()];
+        let item = &order.items[item.parse::<usize>().unwrap()];
+        self.status = match self.scraper.remove_from_cart(order, item) {
+            true => format!("Removed {}", item.name),
+            false => format!("Unable to remove {} from the cart", item.name),
+        };
+        let status = std::mem::take(&mut self.status);
+        self.open_cart();
+        self.status = status;
+    }
+
+    fn breadcrumb(&self) -> String {
+        self.stack
+            .iter()
+            .map(|pane| pane.title.as_str())
+            .collect::<Vec<&str>>()
+            .join(" > ")
+    }
+}
+
+fn draw(frame: &mut Frame, app: &mut App) {
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .split(frame.size());
+
+    frame.render_widget(
+        Paragraph::new(app.breadcrumb()).style(Style::default().fg(Color::Blue).bold()),
+        layout[0],
+    );
+
+    let searching = app.searching;
+    let status = app.status.clone();
+    let pane = app.pane();
+    let header = Row::new(pane.header.iter().enumerate().map(|(i, name)| {
+        let name = match pane.sort {
+            Some((column, false)) if column == i => format!("{} ▲", name),
+            Some((column, true)) if column == i => format!("{} ▼", name),
+            _ => name.to_string(),
+        };
+        Cell::from(name)
+    }))
+    .style(Style::default().fg(Color::Red).bold());
+    let rows = pane.visible.iter().map(|&i| {
+        let row = &pane.rows[i];
+        let height = row
+            .iter()
+            .map(|cell| cell.lines().count())
+            .max()
+            .unwrap_or(1);
+        Row::new(row.iter().map(|cell| Cell::from(cell.as_str()))).height(height.max(1) as u16)
+    });
+    let widths: Vec<Constraint> = pane
+        .widths
+        .iter()
+        .map(|w| Constraint::Percentage(*w))
+        .collect();
+    let table = Table::new(rows)
+        .header(header)
+        .widths(&widths)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Blue))
+                .title(format!(" {} ({}) ", pane.title, pane.visible.len())),
+        )
+        .highlight_style(Style::default().reversed());
+    frame.render_stateful_widget(table, layout[1], &mut pane.state);
+
+    let line = match (searching, pane.search.is_empty()) {
+        (true, _) => format!("/{}", pane.search),
+        (false, false) => format!("search: {}  {}", pane.search, status),
+        (false, true) => status,
+    };
+    frame.render_widget(Paragraph::new(line), layout[2]);
+    frame.render_widget(
+        Paragraph::new(HELP).style(Style::default().fg(Color::DarkGray)),
+        layout[3],
+    );
+}
+
+/// Handles a key press. Returns false when the user quits.
+fn handle_key(app: &mut App, code: KeyCode) -> bool {
+    if app.searching {
+        let pane = app.pane();
+        match code {
+            KeyCode::Char(c) => pane.search.push(c),
+            KeyCode::Backspace => {
+                pane.search.pop();
+            }
+            KeyCode::Esc => {
+                pane.search.clear();
+                app.searching = false;
+            }
+            KeyCode::Enter => app.searching = false,
+            KeyCode::Up => pane.move_by(-1),
+            KeyCode::Down => pane.move_by(1),
+            _ => {}
+        }
+        app.pane().refresh();
+        return true;
+    }
+    match code {
+        KeyCode::Char('q') => return false,
+        KeyCode::Up | KeyCode::Char('k') => app.pane().move_by(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.pane().move_by(1),
+        KeyCode::PageUp => app.pane().move_by(-10),
+        KeyCode::PageDown => app.pane().move_by(10),
+        KeyCode::Enter | KeyCode::Right => app.open_selected(),
+        KeyCode::Esc | KeyCode::Backspace | KeyCode::Left if app.stack.len() > 1 => {
+            app.stack.pop();
+            app.status.clear();
+        }
+        KeyCode::Char('/') => {
+            app.searching = true;
+            app.pane().search.clear();
+            app.pane().refresh();
+        }
+        KeyCode::Char('s') => app.pane().cycle_sort(),
+        KeyCode::Char('r') => app.pane().reverse_sort(),
+        KeyCode::Char('a') => app.add_selected(),
+        KeyCode::Char('c') => app.open_cart(),
+        KeyCode::Char('d') => app.remove_selected(),
+        _ => {}
+    }
+    true
+}
+
+fn restore_terminal() {
+    disable_raw_mode().ok();
+    execute!(io::stdout(), LeaveAlternateScreen).ok();
+}
+
+fn run(terminal: &mut Terminal<CrosstermBackend<Stdout>>, app: &mut App) -> io::Result<()> {
+    loop {
+        terminal.draw(|frame| draw(frame, app))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            // Scraping blocks, so tell the user before it starts.
+            if matches!(
+                key.code,
+                KeyCode::Enter | KeyCode::Right | KeyCode::Char('c')
+            ) && !app.searching
+            {
+                app.status = String::from("Loading...");
+                terminal.draw(|frame| draw(frame, app))?;
+            }
+            if !handle_key(app, key.code) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+pub fn run_tui(
+    scraper: &DiscogsScraper,
+    query: Option<String>,
+    filter: ConditionFilter,
+    view: &TableView,
+) {
+    // Failing to read the wantlist or the API token exits, so both are read before the screen is
+    // set up. The notes are read from the API, so only when a column shows them.
+    let mut items = scraper.get_wantlist(Some(query.unwrap_or_default()));
+    scraper.connect();
+    if view.shows_notes() {
+        scraper.load_notes(&mut items);
+    }
+    if !view.hides("Budget") {
+        scraper.check_budget(&mut items);
+    }
+    let links = items.iter().map(|item| item.sellers_link.clone()).collect();
+    let rows = items.iter().map(WantlistItem::row).collect();
+    let wantlist = Pane::new(
+        View::Wantlist,
+        String::from("Wantlist"),
+        WANTLIST_HEADER,
+        &[9, 36, 15, 6, 16, 10, 8],
+        links,
+        rows,
+    );
+    let mut app = App {
+        scraper,
+        filter,
+        items,
+        stack: vec![wantlist],
+        orders: Vec::new(),
+        searching: false,
+        status: String::new(),
+    };
+
+    // Scraping errors are shown in the status line, but failed requests still panic. Leave the
+    // terminal usable when they do, and keep warnings out of the screen.
+    scraper.collect_warnings();
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        hook(info);
+    }));
+    enable_raw_mode().expect("Failed to set up the terminal.");
+    execute!(io::stdout(), EnterAlternateScreen).expect("Failed to set up the terminal.");
+    let mut terminal =
+        Terminal::new(CrosstermBackend::new(io::stdout())).expect("Failed to set up the terminal.");
+    let result = run(&mut terminal, &mut app);
+    restore_terminal();
+    result.expect("Terminal error.");
+}
@@ -0,0 +1,100 @@
+use crate::cli::{self, TableType, TableView, VersionMenu};
+use crate::release;
+use crate::web::{master_id_from_link, ConditionFilter, DiscogsScraper};
+
+const VERSIONS_HEADER: &[&str] = &[
+    "Title", "Format", "Label", "Catalog#", "Country", "Year", "Have", "Want",
+];
+
+/// Id of the master given by `master`: its id with `is_id`, a master link, or else an album name
+/// to search, as names can be numbers too.
+fn find_master(scraper: &DiscogsScraper, master: &str, is_id: bool) -> u64 {
+    if is_id {
+        return master.parse().unwrap_or_else(|_| {
+            println!("{} is not a valid master id", master);
+            std::process::exit(1);
+        });
+    }
+    let link = match master.contains("/master/") {
+        true => master.to_string(),
+        false => cli::select_master(scraper, master),
+    };
+    master_id_from_link(&link).expect("Unable to read the master release id.")
+}
+
+pub fn versions_command(
+    scraper: &DiscogsScraper,
+    master: &str,
+    is_id: bool,
+    filter: &ConditionFilter,
+    view: &TableView,
+) {
+    let master_id = find_master(scraper, master, is_id);
+    browse_versions(scraper, master_id, filter, view);
+}
+
+/// Lists the versions of a master, to add some of them to the wantlist, see who sells one or
+/// show its details.
+pub fn browse_versions(
+    scraper: &DiscogsScraper,
+    master_id: u64,
+    filter: &ConditionFilter,
+    view: &TableView,
+) {
+    let (ids, table) = scraper.get_master_versions(master_id);
+    if ids.is_empty() {
+        println!("No versions of master {}", master_id);
+        return;
+    }
+    let mut versions_view = view.clone();
+    let mut print_versions = true;
+    loop {
+        if print_versions {
+            cli::print_view(
+                VERSIONS_HEADER,
+                &table,
+                &format!("Master {} Versions", master_id),
+                TableType::Default,
+                &versions_view,
+            );
+        }
+        print_versions = false;
+        match cli::select_version_action() {
+            VersionMenu::AddToWantlist => {
+                let selected = cli::select_rows(
+                    "Select versions to add:",
+                    VERSIONS_HEADER,
+                    &table,
+                    &versions_view,
+                );
+                if selected.is_empty() {
+                    continue;
+                }
+                let selected_ids = selected.iter().map(|&i| ids[i] as i64).collect();
+                match scraper.add_releases_to_wantlist(selected_ids) {
+                    Ok(added) => println!("Added {} items to wantlist.", added),
+                    Err(messages) => println!("{:#?}", messages),
+                }
+            }
+            VersionMenu::Listings => {
+                let selected =
+                    cli::select_row("Select a version:", VERSIONS_HEADER, &table, &versions_view);
+                if let Some(index) = selected {
+                    release::show_listings(scraper, ids[index], &table[index][0], filter, view);
+                }
+            }
+            VersionMenu::Details => {
+                let selected =
+                    cli::select_row("Select a version:", VERSIONS_HEADER, &table, &versions_view);
+                if let Some(index) = selected {
+                    release::show_release(scraper, ids[index]);
+                }
+            }
+            VersionMenu::View => {
+                versions_view.edit(VERSIONS_HEADER);
+                print_versions = true;
+            }
+            VersionMenu::Exit => return,
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use crate::web::{ConditionFilter, DiscogsScraper, Listing, Price};
+use owo_colors::{OwoColorize, Stream};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+#[derive(Debug)]
+pub struct WatchOptions {
+    pub query: Option<String>,
+    pub max_price: Option<Price>,
+    pub item_max: HashMap<u64, Price>,
+    pub state: String,
+    pub interval: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SeenListing {
+    seller: String,
+    price: Option<Price>,
+}
+
+/// Listings seen in previous scans, by release and then by listing id.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WatchState {
+    items: HashMap<String, HashMap<String, SeenListing>>,
+}
+
+impl WatchState {
+    /// Reads the state of previous scans. A state file that can't be read is replaced by a fresh
+    /// state, so every listing is reported as new once.
+    fn load(path: &str) -> WatchState {
+        let Ok(data) = std::fs::read_to_string(path) else {
+            return WatchState::default();
+        };
+        serde_json::from_str(&data).unwrap_or_else(|e| {
+            println!(
+                "Unable to parse watch state file {} ({}), starting over",
+                path, e
+            );
+            WatchState::default()
+        })
+    }
+
+    fn save(&self, path: &str) {
+        let data = serde_json::to_string_pretty(self).unwrap();
+        std::fs::write(path, data).expect("Unable to write watch state file.");
+    }
+}
+
+enum Alert {
+    UnderMax(Price),
+    NewSeller,
+    PriceDrop(Price),
+}
+
+fn report(title: &str, listing: &Listing, alert: Alert) {
+    let label = match alert {
+        Alert::UnderMax(max) => format!("[under {}]", max.to_string().trim())
+            .if_supports_color(Stream::Stdout, |s| s.green())
+            .to_string(),
+        Alert::NewSeller => "[new seller]"
+            .if_supports_color(Stream::Stdout, |s| s.cyan())
+            .to_string(),
+        Alert::PriceDrop(previous) => format!("[was {}]", previous)
+            .if_supports_color(Stream::Stdout, |s| s.yellow())
+            .to_string(),
+    };
+    println!(
+        "{} {} | {} | {} | {}\n    {}",
+        label,
+        title,
+        listing.seller,
+        listing.grading.to_string().replace('\n', " "),
+        listing.price,
+        listing.url()
+    );
+}
+
+fn scan(scraper: &DiscogsScraper, options: &WatchOptions, filter: &ConditionFilter) {
+    let mut state = WatchState::load(&options.state);
+    // Notes may have changed since the previous scan.
+    scraper.forget_wantlist();
+    let items = scraper.watched_items(options.query.as_deref());
+    for item in items.iter().filter(|item| !item.sellers_link.is_empty()) {
+        let key = match item.release_id {
+            Some(id) => id.to_string(),
+            None => item.sellers_link.clone(),
+        };
+        // --item-max comes first, then a "max:" in the notes of the item and then --max-price.
+        let max = match item.release_id.and_then(|id| options.item_max.get(&id)) {
+            Some(max) => Some(max.clone()),
+            None => item.max_price().or(options.max_price.clone()),
+        };
+        let listings = match scraper.get_listings(&item.sellers_link, filter) {
+            Ok(listings) => listings,
+            Err(e) => {
+                println!("{}", e);
+                continue;
+            }
+        };
+        match state.items.get(&key) {
+            Some(previous) => {
+                let known_sellers: HashSet<&str> =
+                    previous.values().map(|seen| seen.seller.as_str()).collect();
+                for listing in listings.iter() {
+                    let price = Price::parse(&listing.price);
+                    match previous.get(&listing.id) {
+                        Some(seen) => {
+                            if let (Some(old), Some(new)) = (&seen.price, &price) {
+                                if old.currency == new.currency && new.value < old.value {
+                                    report(&item.title, listing, Alert::PriceDrop(old.clone()));
+                                }
+                            }
+                        }
+                        None => {
+                            if let Some(max) = &max {
+                                if scraper.within_max(listing, max) == Some(true) {
+                                    report(&item.title, listing, Alert::UnderMax(max.clone()));
+                                    continue;
+                                }
+                            }
+                            if !known_sellers.contains(listing.seller.as_str()) {
+                                report(&item.title, listing, Alert::NewSeller);
+                            }
+                        }
+                    }
+                }
+            }
+            None => {
+                println!("Now watching {} ({} listings)", item.title, listings.len());
+                if let Some(max) = &max {
+                    for listing in listings
+                        .iter()
+                        .filter(|listing| scraper.within_max(listing, max) == Some(true))
+                    {
+                        report(&item.title, listing, Alert::UnderMax(max.clone()));
+                    }
+                }
+            }
+        }
+        let seen = listings
+            .into_iter()
+            .map(|listing| {
+                let price = Price::parse(&listing.price);
+                (
+                    listing.id,
+                    SeenListing {
+                        seller: listing.seller,
+                        price,
+                    },
+                )
+            })
+            .collect();
+        state.items.insert(key, seen);
+        // Save after every release so an interrupted scan does not report the same changes again.
+        state.save(&options.state);
+    }
+}
+
+pub fn watch(scraper: &DiscogsScraper, options: &WatchOptions, filter: &ConditionFilter) {
+    loop {
+        scan(scraper, options, filter);
+        match options.interval {
+            Some(minutes) => std::thread::sleep(Duration::from_secs(minutes * 60)),
+            None => break,
+        }
+    }
+}
@@ -1,58 +1,302 @@
 use super::types::*;
-use super::DiscogsScraper;
+use super::{form_fields, DiscogsScraper};
 use itertools::Itertools;
+use reqwest::header::LOCATION;
 
 const CART: &str = "sell/cart";
+const FLASH_MESSAGES: &str = "div.alert, div.alert-message, div.message, ul.messages li";
+
+fn parse_item(item: scraper::ElementRef) -> CartItem {
+    let link = item.get_link("td.order-item-info a.item_link");
+    let selector = scraper::Selector::parse("[name*='Remove']").unwrap();
+    let remove = match item.select(&selector).next() {
+        Some(button) => Some(RemoveControl::Button(
+            button.value().attr("name").unwrap().to_string(),
+            button.value().attr("value").unwrap_or("").to_string(),
+        )),
+        None => match item.get_link("a[href*='remove']") {
+            link if link.is_empty() => None,
+            link => Some(RemoveControl::Link(link)),
+        },
+    };
+    CartItem {
+        id: link
+            .rsplit('/')
+            .next()
+            .unwrap_or("")
+            .split(|c: char| !c.is_ascii_digit())
+            .next()
+            .unwrap_or("")
+            .to_string(),
+        name: item.get_inner_text("td.order-item-info a.item_link"),
+        grading: Grading::parse(&item.get_inner_text("td.order-item-info span.item_condition")),
+        link,
+        price: item.get_inner_text("td.price"),
+        remove,
+    }
+}
+
+/// VAT and fee rows of an order summary, which only some sellers and countries have.
+fn parse_fees(order: &scraper::ElementRef) -> Vec<(String, Price)> {
+    let selector = scraper::Selector::parse("div.order_summary tr").unwrap();
+    order
+        .select(&selector)
+        .filter_map(|row| {
+            let label = row.get_inner_text("td.order_summary_label, th");
+            let lowercase = label.to_lowercase();
+            if !["vat", "tax", "fee"].iter().any(|w| lowercase.contains(w)) {
+                return None;
+            }
+            Price::parse(&row.get_inner_text("td.order_summary_value"))
+                .map(|price| (label.trim_end_matches(':').to_string(), price))
+        })
+        .collect()
+}
+
+fn cart_count(orders: &[CartOrder]) -> usize {
+    orders.iter().map(|order| order.items.len()).sum()
+}
+
+/// Reads what happened from the flash messages of the page shown after adding a listing.
+fn read_outcome(page: &scraper::Html, listing_id: &str) -> Option<CartOutcome> {
+    let message = page.root_element().get_inner_text(FLASH_MESSAGES);
+    let lowercase = message.to_lowercase();
+    if lowercase.contains("sold") || lowercase.contains("no longer available") {
+        Some(CartOutcome::Sold(message))
+    } else if lowercase.contains("already") {
+        Some(CartOutcome::AlreadyInCart)
+    } else if lowercase.contains("log in") || lowercase.contains("sign in") {
+        Some(CartOutcome::SessionExpired)
+    } else if !listing_id.is_empty()
+        && !page
+            .root_element()
+            .get_link(&format!("a[href*='/sell/item/{}']", listing_id))
+            .is_empty()
+    {
+        Some(CartOutcome::Added)
+    } else if !message.is_empty() {
+        Some(CartOutcome::Failed(message))
+    } else {
+        None
+    }
+}
 
 impl DiscogsScraper {
-    pub fn get_cart(&self) -> (Vec<String>, Vec<Vec<Vec<String>>>) {
+    /// Adds a listing through its add-to-cart link. The listing was added when the cart holds one
+    /// more item than before; otherwise the messages of the page tell why not.
+    pub fn add_to_cart(&self, link: &str) -> CartOutcome {
+        let before = cart_count(&self.read_cart());
+        let link = link
+            .trim_start_matches(super::WEB_HOME_URL)
+            .trim_start_matches('/');
+        let listing_id = link
+            .rsplit(|c: char| !c.is_ascii_digit())
+            .find(|s| !s.is_empty())
+            .unwrap_or("");
+        let res = self
+            .web
+            .get(link)
+            .send()
+            .expect("Failed to process request.");
+        let status = res.status();
+        let location = res
+            .headers()
+            .get(LOCATION)
+            .and_then(|l| l.to_str().ok())
+            .map(str::to_string);
+        let body = match &location {
+            Some(location) if location.contains("login") => return CartOutcome::SessionExpired,
+            Some(location) => self
+                .web
+                .get(
+                    location
+                        .trim_start_matches(super::WEB_HOME_URL)
+                        .trim_start_matches('/'),
+                )
+                .send_request(),
+            None => res.text().unwrap(),
+        };
+        let page = scraper::Html::parse_document(&body);
+        let hint = read_outcome(&page, listing_id);
+        let cart = self.read_cart();
+        if cart_count(&cart) > before {
+            return CartOutcome::Added;
+        }
+        let in_cart = !listing_id.is_empty()
+            && cart
+                .iter()
+                .flat_map(|order| order.items.iter())
+                .any(|item| item.id == listing_id);
+        match hint {
+            Some(CartOutcome::Added) | None if in_cart => CartOutcome::AlreadyInCart,
+            Some(CartOutcome::Added) | None if status.as_u16() == 404 || status.as_u16() == 410 => {
+                CartOutcome::Sold(String::from("Listing not found"))
+            }
+            Some(CartOutcome::Added) | None => {
+                CartOutcome::Failed(format!("The cart did not change ({})", status))
+            }
+            Some(outcome) => outcome,
+        }
+    }
+
+    pub fn get_cart(&self) -> Vec<CartOrder> {
+        let orders = self.read_cart();
+        if let Some(db) = &self.db {
+            db.record_cart(&orders);
+        }
+        orders
+    }
+
+    /// The cart as Discogs shows it now, without recording it.
+    fn read_cart(&self) -> Vec<CartOrder> {
         let res = self.web.get(CART);
         let cart_page = scraper::Html::parse_document(&res.send_request());
         let selector = scraper::Selector::parse("div.orders form").unwrap();
-        let mut tabels: Vec<Vec<Vec<String>>> = Vec::new();
-        let mut sellers: Vec<String> = Vec::new();
+        let mut orders: Vec<CartOrder> = Vec::new();
         for node in cart_page.select(&selector) {
-            let mut table: Vec<Vec<String>> = Vec::new();
             let selector = scraper::Selector::parse("table.order_list_table tr.order_row").unwrap();
-            for item in node.select(&selector) {
-                let name = item.get_inner_text("td.order-item-info a.item_link");
-                let condition = item
-                    .get_inner_text("td.order-item-info span.item_condition")
-                    .split("   ")
-                    .enumerate()
-                    .filter_map(|(i, c)| if i != 1 { Some(c) } else { None })
-                    .join("")
-                    .split_whitespace()
-                    .join(" ");
-                let link = item.get_link("td.order-item-info a.item_link");
-                let price = item.get_inner_text("td.price");
-                table.push(vec![
-                    format!("{}\n{}\n{}{}", name, condition, super::WEB_HOME_URL, link),
-                    price,
-                ]);
-            }
-            let subtotal =
-                node.get_inner_text("div.order_summary tr.order_subtotal td.order_summary_value");
-            let subtotal_price: f32 = subtotal.split(" ").next().unwrap()[3..].parse().unwrap();
-            table.push(vec![String::from("Subtotal"), subtotal]);
+            let items = node.select(&selector).map(parse_item).collect();
+            let selector =
+                scraper::Selector::parse("div.order_summary select.shipping_method").unwrap();
+            let shipping_field = node
+                .select(&selector)
+                .next()
+                .and_then(|select| select.value().attr("name"))
+                .map(str::to_string);
             let selector =
                 scraper::Selector::parse("div.order_summary select.shipping_method option")
                     .unwrap();
-            for option in node.select(&selector) {
-                let description = option.get_text();
-                let parsed_price: f32 =
-                    option.value().attr("data-amount").unwrap().parse().unwrap();
-                let total_price = subtotal_price + parsed_price;
-                table.push(vec![description, format!("€{:.2} EUR", total_price)]);
+            let shipping = node
+                .select(&selector)
+                .map(|option| ShippingOption {
+                    description: option.get_text(),
+                    value: option.value().attr("value").unwrap_or("").to_string(),
+                    amount: option.value().attr("data-amount").unwrap().parse().unwrap(),
+                    selected: option.value().attr("selected").is_some(),
+                })
+                .collect();
+            orders.push(CartOrder {
+                seller: node.get_inner_text("div.box-header-row span.linked_username"),
+                rating: node
+                    .get_inner_text("div.box-header-row span.inline_rating small")
+                    .split_whitespace()
+                    .join(" "),
+                items,
+                subtotal: node
+                    .get_inner_text("div.order_summary tr.order_subtotal td.order_summary_value"),
+                converted_subtotal: Price::parse(
+                    &node
+                        .get_inner_text("div.order_summary tr.order_subtotal span.converted_price"),
+                ),
+                shipping,
+                fees: parse_fees(&node),
+                shipping_field,
+                action: node.value().attr("action").unwrap_or(CART).to_string(),
+                fields: form_fields(&node),
+            });
+        }
+        orders
+    }
+
+    /// The order of a seller, read again from the cart.
+    fn current_order(&self, seller: &str) -> Option<CartOrder> {
+        self.read_cart()
+            .into_iter()
+            .find(|order| order.seller == seller)
+    }
+
+    /// Posts the order form as a browser would, with `changes` replacing or adding fields.
+    /// Returns whether Discogs accepted the form.
+    fn submit_order(&self, order: &CartOrder, changes: &[(String, String)]) -> bool {
+        let mut fields: Vec<(String, String)> = order
+            .fields
+            .iter()
+            .filter(|(name, _)| !changes.iter().any(|(changed, _)| changed == name))
+            .cloned()
+            .collect();
+        fields.extend(changes.iter().cloned());
+        self.post_form(&order.action, &fields)
+    }
+
+    /// Sends the removal of an item, without checking the cart afterwards.
+    fn send_removal(&self, order: &CartOrder, item: &CartItem) -> bool {
+        match &item.remove {
+            Some(RemoveControl::Button(name, value)) => {
+                self.submit_order(order, &[(name.clone(), value.clone())])
             }
-            tabels.push(table);
-            let seller_name = node.get_inner_text("div.box-header-row span.linked_username");
-            let seller_rating = node
-                .get_inner_text("div.box-header-row span.inline_rating small")
-                .split_whitespace()
-                .join(" ");
-            sellers.push(format!("{} {}", seller_name, seller_rating));
+            Some(RemoveControl::Link(link)) => {
+                let res = self
+                    .web
+                    .get(
+                        link.trim_start_matches(super::WEB_HOME_URL)
+                            .trim_start_matches('/'),
+                    )
+                    .send()
+                    .expect("Failed to process request.");
+                res.status().is_success() || res.status().is_redirection()
+            }
+            None => false,
         }
-        (sellers, tabels)
+    }
+
+    /// Removes an item, checking the cart afterwards to confirm it is gone.
+    pub fn remove_from_cart(&self, order: &CartOrder, item: &CartItem) -> bool {
+        self.send_removal(order, item)
+            && self
+                .current_order(&order.seller)
+                .is_none_or(|order| !order.items.iter().any(|i| i.link == item.link))
+    }
+
+    /// Removes every item of the order. Each removal changes the order form, so the order is read
+    /// again before the next one. Returns the number of items removed.
+    pub fn empty_order(&self, order: &CartOrder) -> usize {
+        let mut removed = 0;
+        let mut current = self.current_order(&order.seller);
+        while let Some(order) = current {
+            let Some(item) = order.items.first() else {
+                break;
+            };
+            if !self.send_removal(&order, item) {
+                break;
+            }
+            current = self.current_order(&order.seller);
+            let gone = current
+                .as_ref()
+                .is_none_or(|order| !order.items.iter().any(|i| i.link == item.link));
+            if !gone {
+                break;
+            }
+            removed += 1;
+        }
+        removed
+    }
+
+    /// Selects a shipping method, checking the cart afterwards to confirm it was applied.
+    pub fn set_shipping(&self, order: &CartOrder, option: &ShippingOption) -> bool {
+        let Some(field) = &order.shipping_field else {
+            return false;
+        };
+        self.submit_order(order, &[(field.clone(), option.value.clone())])
+            && self.current_order(&order.seller).is_some_and(|order| {
+                order
+                    .shipping
+                    .iter()
+                    .any(|o| o.selected && o.value == option.value)
+            })
+    }
+
+    /// Release of a cart item, read from the listing page when the item links to it.
+    pub fn cart_item_release(&self, item: &CartItem) -> Option<u64> {
+        release_id_from_link(&item.link).or_else(|| {
+            let (body, _) = self
+                .fetch_page(
+                    item.link
+                        .trim_start_matches(super::WEB_HOME_URL)
+                        .trim_start_matches('/'),
+                )
+                .ok()?;
+            let page = scraper::Html::parse_document(&body);
+            release_id_from_link(&page.root_element().get_link("a[href*='/release/']"))
+        })
     }
 }
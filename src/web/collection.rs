@@ -0,0 +1,96 @@
+use super::types::*;
+use super::DiscogsScraper;
+
+impl DiscogsScraper {
+    pub fn collection_folders(&self) -> Vec<Folder> {
+        let folders: Folders = self
+            .api_get(&format!("users/{}/collection/folders", self.username()))
+            .send_request_json();
+        folders.folders
+    }
+
+    pub fn collection_fields(&self) -> Vec<CollectionField> {
+        let fields: CollectionFields = self
+            .api_get(&format!("users/{}/collection/fields", self.username()))
+            .send_request_json();
+        fields.fields
+    }
+
+    /// Every item of a folder (0 is the folder with the whole collection), going through all the
+    /// pages of results.
+    pub fn collection_items(&self, folder_id: u64) -> Vec<CollectionItem> {
+        let path = format!(
+            "users/{}/collection/folders/{}/releases",
+            self.username(),
+            folder_id
+        );
+        self.api_pages(&path, &[], usize::MAX, |page: CollectionPage| {
+            (page.pagination, page.releases)
+        })
+    }
+
+    /// Copies of a release in the collection, in any folder.
+    pub fn collection_instances(&self, release_id: u64) -> Vec<CollectionItem> {
+        let instances: CollectionInstances = self
+            .api_get(&format!(
+                "users/{}/collection/releases/{}",
+                self.username(),
+                release_id
+            ))
+            .send_request_json();
+        instances.releases
+    }
+
+    /// Adds a release to a folder. Returns the id of the new copy, or None if Discogs refused.
+    pub fn add_to_collection(&self, folder_id: u64, release_id: u64) -> Option<u64> {
+        let res = self
+            .api_post(&format!(
+                "users/{}/collection/folders/{}/releases/{}",
+                self.username(),
+                folder_id,
+                release_id
+            ))
+            .send()
+            .expect("Failed to process request.");
+        if !res.status().is_success() {
+            return None;
+        }
+        res.json::<AddedInstance>()
+            .ok()
+            .map(|added| added.instance_id)
+    }
+
+    fn instance_url(&self, item: &CollectionItem) -> String {
+        format!(
+            "users/{}/collection/folders/{}/releases/{}/instances/{}",
+            self.username(),
+            item.folder_id,
+            item.id,
+            item.instance_id
+        )
+    }
+
+    pub fn move_collection_item(&self, item: &CollectionItem, folder_id: u64) -> bool {
+        self.api_post(&self.instance_url(item))
+            .json(&serde_json::json!({ "folder_id": folder_id }))
+            .send_request_succeeds()
+    }
+
+    pub fn remove_from_collection(&self, item: &CollectionItem) -> bool {
+        self.api_delete(&self.instance_url(item))
+            .send_request_succeeds()
+    }
+
+    /// Sets the rating of a copy, from 1 to 5 (0 clears it).
+    pub fn rate_collection_item(&self, item: &CollectionItem, rating: u8) -> bool {
+        self.api_post(&self.instance_url(item))
+            .json(&serde_json::json!({ "rating": rating }))
+            .send_request_succeeds()
+    }
+
+    pub fn edit_collection_field(&self, item: &CollectionItem, field_id: u64, value: &str) -> bool {
+        self.api_post(&format!("{}/fields/{}", self.instance_url(item), field_id))
+            .query(&[("value", value)])
+            .send_request_succeeds()
+    }
+}
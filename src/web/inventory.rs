@@ -0,0 +1,83 @@
+use super::types::*;
+use super::DiscogsScraper;
+
+impl DiscogsScraper {
+    /// Every listing of the user's inventory, going through all the pages of results.
+    pub fn get_inventory(&self) -> Vec<InventoryListing> {
+        let path = format!("users/{}/inventory", self.username());
+        self.api_pages(&path, &[], usize::MAX, |page: InventoryPage| {
+            (page.pagination, page.listings)
+        })
+    }
+
+    /// Puts a release up for sale. Returns the id of the new listing, or the error returned by
+    /// Discogs.
+    pub fn create_listing(
+        &self,
+        release_id: u64,
+        grading: &Grading,
+        price: f32,
+        comments: &str,
+    ) -> Result<u64, String> {
+        let mut listing = serde_json::json!({
+            "release_id": release_id,
+            "condition": grading.media.map(|c| c.api_name()),
+            "price": price,
+            "comments": comments,
+            "status": "For Sale",
+        });
+        if let Some(sleeve) = grading.sleeve {
+            listing["sleeve_condition"] = serde_json::Value::from(sleeve.api_name());
+        }
+        let res = self
+            .api_post("marketplace/listings")
+            .json(&listing)
+            .send()
+            .expect("Failed to process request.");
+        match res.status().is_success() {
+            true => res
+                .json::<CreatedListing>()
+                .map(|created| created.listing_id)
+                .map_err(|e| e.to_string()),
+            false => Err(res.text().unwrap_or_default()),
+        }
+    }
+
+    /// Changes the price of a listing, keeping everything else as it is.
+    pub fn set_listing_price(&self, listing: &InventoryListing, price: f32) -> bool {
+        let mut edit = serde_json::json!({
+            "release_id": listing.release.id,
+            "condition": listing.condition,
+            "price": price,
+            "comments": listing.comments,
+            "status": listing.status,
+        });
+        if let Some(sleeve) = &listing.sleeve_condition {
+            edit["sleeve_condition"] = serde_json::Value::from(sleeve.as_str());
+        }
+        // Fields left out of an edit are reset, so the current ones are sent again.
+        if let Some(allow_offers) = listing.allow_offers {
+            edit["allow_offers"] = serde_json::Value::from(allow_offers);
+        }
+        if let Some(location) = &listing.location {
+            edit["location"] = serde_json::Value::from(location.as_str());
+        }
+        if let Some(external_id) = &listing.external_id {
+            edit["external_id"] = serde_json::Value::from(external_id.as_str());
+        }
+        if let Some(weight) = &listing.weight {
+            edit["weight"] = weight.clone();
+        }
+        if let Some(format_quantity) = &listing.format_quantity {
+            edit["format_quantity"] = format_quantity.clone();
+        }
+        self.api_post(&format!("marketplace/listings/{}", listing.id))
+            .json(&edit)
+            .send_request_succeeds()
+    }
+
+    pub fn delete_listing(&self, listing_id: u64) -> bool {
+        self.api_delete(&format!("marketplace/listings/{}", listing_id))
+            .send_request_succeeds()
+    }
+}
@@ -11,11 +11,10 @@ const REMOVE_SHA256HASH: &str = "ab4a277f4c5d9da56ba17d4b88643c51a1935f500813133
 
 impl DiscogsScraper {
     pub fn search_release(&self, search: &str) -> (Vec<String>, Vec<Vec<String>>) {
-        let url = format!(
-            "search/?q={}&type=master&layout=sm",
-            search.replace(" ", "+")
-        );
-        let res = self.web.get(&url);
+        let res =
+            self.web
+                .get("search/")
+                .query(&[("q", search), ("type", "master"), ("layout", "sm")]);
         let search_page = scraper::Html::parse_document(&res.send_request());
         let selector = scraper::Selector::parse("li.card div.card_body").unwrap();
         let mut table: Vec<Vec<String>> = Vec::new();
@@ -34,12 +33,58 @@ impl DiscogsScraper {
         (links, table)
     }
 
-    fn graphql_post_request(&self, url: &str, operation: &str, sha256hash: &str) -> String {
+    /// Results of a database search, reading at most `pages` pages of results.
+    pub fn search_database(&self, query: &SearchQuery, pages: usize) -> Vec<SearchResult> {
+        self.api_pages(
+            "database/search",
+            &query.params(),
+            pages,
+            |page: SearchPage| (page.pagination, page.results),
+        )
+    }
+
+    /// Releases with this barcode, or this catalog number when `barcode` is false.
+    pub fn search_identifier(&self, code: &str, barcode: bool) -> Vec<SearchResult> {
+        let code = Some(code.to_string());
+        let query = match barcode {
+            true => SearchQuery {
+                barcode: code,
+                ..Default::default()
+            },
+            false => SearchQuery {
+                catno: code,
+                ..Default::default()
+            },
+        };
+        self.search_database(
+            &SearchQuery {
+                kind: String::from("release"),
+                ..query
+            },
+            1,
+        )
+    }
+
+    /// Every master and release listed under an artist or a label, with `path` being
+    /// "artists/{id}/releases" or "labels/{id}/releases".
+    pub fn get_discography(&self, path: &str) -> Vec<DiscographyItem> {
+        let sort = [("sort", "year"), ("sort_order", "asc")];
+        self.api_pages(path, &sort, usize::MAX, |page: DiscographyPage| {
+            (page.pagination, page.releases)
+        })
+    }
+
+    /// Ids of the LP releases of a master.
+    fn lp_ids(&self, url: &str) -> Vec<i64> {
         let master_release_id = url.split("-").next().unwrap().to_string() + GETLP;
         let res = self.web.get(&master_release_id);
         let results: LPRelease = res.send_request_json();
+        results.get_ids()
+    }
+
+    fn graphql_post_request(&self, ids: Vec<i64>, operation: &str, sha256hash: &str) -> String {
         let extensions = Extensions::new(operation, sha256hash, VERSION);
-        let variables = Variables::new(results.get_ids());
+        let variables = Variables::new(ids);
         let add_wantlist = AddWantlist {
             extensions,
             variables,
@@ -48,38 +93,82 @@ impl DiscogsScraper {
             .web
             .post(GRAPHQL_URL)
             .body(serde_json::to_string(&add_wantlist).unwrap());
-        res.send_request()
+        let response = res.send_request();
+        self.forget_wantlist();
+        response
     }
 
     pub fn add_lps_to_wantlist(&self, url: &str) {
-        let response = self.graphql_post_request(url, ADD_OPERATION_NAME, ADD_SHA256HASH);
+        match self.add_releases_to_wantlist(self.lp_ids(url)) {
+            Ok(added) => println!("Added {} items to wantlist.", added),
+            Err(messages) => println!("{:#?}", messages),
+        }
+    }
+
+    /// Adds releases to the wantlist. Returns how many were added, or the error messages of a
+    /// rejected request.
+    pub fn add_releases_to_wantlist(&self, ids: Vec<i64>) -> Result<usize, Vec<String>> {
+        let response = self.graphql_post_request(ids, ADD_OPERATION_NAME, ADD_SHA256HASH);
         match serde_json::from_str::<ErrorMessage>(&response) {
-            Ok(e) => println!("{:#?}", e.get_messages()),
+            Ok(e) => Err(e.get_messages()),
             Err(_) => {
                 let success_body: serde_json::Value =
                     serde_json::from_str(&response).expect("Can't parse json");
                 let objects =
                     &success_body["data"]["addReleasesToWantlist"]["wantlistItems"].to_string();
-                let items_added: Vec<AddedItems> = serde_json::from_str(&objects).unwrap();
-                println!("Added {} items to wantlist.", items_added.len());
+                let items_added: Vec<AddedItems> = serde_json::from_str(objects).unwrap();
+                Ok(items_added.len())
             }
         }
     }
 
+    /// Every version of a master release, with the release ids in the same order as the rows
+    /// (title, format, label, catalog number, country, year, have, want).
+    pub fn get_master_versions(&self, master_id: u64) -> (Vec<u64>, Vec<Vec<String>>) {
+        let path = format!("masters/{}/versions", master_id);
+        self.api_pages(&path, &[], usize::MAX, |page: MasterVersions| {
+            (page.pagination, page.versions)
+        })
+        .into_iter()
+        .map(|version| {
+            let community = version.stats.map(|stats| stats.community);
+            let row = vec![
+                version.title,
+                version.format,
+                version.label,
+                version.catno,
+                version.country,
+                version.released,
+                community
+                    .as_ref()
+                    .map_or(String::new(), |c| c.in_collection.to_string()),
+                community.map_or(String::new(), |c| c.in_wantlist.to_string()),
+            ];
+            (version.id, row)
+        })
+        .unzip()
+    }
+
     pub fn remove_all_wantlist(&self, url: &str) {
-        let response = self.graphql_post_request(url, REMOVE_OPERATION_NAME, REMOVE_SHA256HASH);
+        match self.remove_releases_from_wantlist(self.lp_ids(url)) {
+            Ok(true) => println!("Items removed"),
+            Ok(false) => println!("Error in removing items"),
+            Err(messages) => println!("{:#?}", messages),
+        }
+    }
+
+    /// Removes releases from the wantlist. Returns whether Discogs reports success, or the
+    /// error messages of a rejected request.
+    pub fn remove_releases_from_wantlist(&self, ids: Vec<i64>) -> Result<bool, Vec<String>> {
+        let response = self.graphql_post_request(ids, REMOVE_OPERATION_NAME, REMOVE_SHA256HASH);
         match serde_json::from_str::<ErrorMessage>(&response) {
-            Ok(e) => println!("{:#?}", e.get_messages()),
+            Ok(e) => Err(e.get_messages()),
             Err(_) => {
                 let success_body: serde_json::Value =
                     serde_json::from_str(&response).expect("Can't parse json");
                 let objects = &success_body["data"]["removeReleasesFromWantlist"].to_string();
-                let items_added: RemovedItems = serde_json::from_str(&objects).unwrap();
-                if items_added.success {
-                    println!("Items removed");
-                } else {
-                    println!("Error in removing items");
-                }
+                let items_removed: RemovedItems = serde_json::from_str(objects).unwrap();
+                Ok(items_removed.success)
             }
         }
     }
@@ -1,19 +1,41 @@
 mod cart;
+mod collection;
+mod inventory;
 mod master;
+mod orders;
+mod stats;
 mod types;
 mod wantlist;
+use crate::db::{self, Database};
+use crate::rates::Converter;
 use itertools::Itertools;
-use reqwest::blocking::Client as ReqwestClient;
-use reqwest::header::{HeaderMap, HeaderValue, COOKIE};
+use reqwest::blocking::{Client as ReqwestClient, RequestBuilder};
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, COOKIE, LOCATION, USER_AGENT};
 use reqwest::redirect;
-use serde_json;
+use serde::de::DeserializeOwned;
+use std::cell::{OnceCell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
 use types::*;
 
+pub use stats::PriceStats;
+pub use types::{
+    cart_totals, master_id_from_link, release_id_from_link, CartItem, CartOrder, CartOutcome,
+    CollectionField, CollectionItem, Condition, ConditionFilter, DiscographyItem, Folder, Grading,
+    InventoryListing, Listing, Price, RandomFilter, SearchQuery, SearchResult, WantlistItem,
+};
+
 const WEB_USER_AGENT: &str =
     "Mozilla/5.0 (X11; Ubuntu; Linux x86_64; rv:109.0) Gecko/20100101 Firefox/116.0";
 const API_USER_AGENT: &str = "Discogs-stats/0.0.1";
-const WEB_HOME_URL: &str = "https://www.discogs.com";
+pub const WEB_HOME_URL: &str = "https://www.discogs.com";
 const API_HOME_URL: &str = "https://api.discogs.com";
+const MARKETPLACE_PAGE: &str = "sell/mywants";
+const MAX_REDIRECTS: usize = 5;
+/// Items asked for in each page of a paginated API resource.
+const PER_PAGE: usize = 100;
+/// Most API requests sent at the same time.
+const CONCURRENT_MAX_REQUESTS: usize = 50;
 
 fn create_cookie_header(path: &str) -> String {
     let data = std::fs::read_to_string(path).expect("Unable to read file");
@@ -22,10 +44,78 @@ fn create_cookie_header(path: &str) -> String {
     parsed_cookies.iter().map(Cookie::to_string).join("; ")
 }
 
+/// Name and value of the inputs a browser would submit with a form.
+fn form_fields(form: &scraper::ElementRef) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+    let selector = scraper::Selector::parse("input[type='hidden'][name]").unwrap();
+    for input in form.select(&selector) {
+        let value = input.value().attr("value").unwrap_or("");
+        fields.push((
+            input.value().attr("name").unwrap().to_string(),
+            value.to_string(),
+        ));
+    }
+    let selector = scraper::Selector::parse("select[name]").unwrap();
+    let option_selector = scraper::Selector::parse("option").unwrap();
+    for select in form.select(&selector) {
+        let options = select.select(&option_selector).collect::<Vec<_>>();
+        let selected = options
+            .iter()
+            .find(|o| o.value().attr("selected").is_some())
+            .or(options.first());
+        if let Some(option) = selected {
+            let value = option.value().attr("value").unwrap_or("");
+            fields.push((
+                select.value().attr("name").unwrap().to_string(),
+                value.to_string(),
+            ));
+        }
+    }
+    fields
+}
+
+/// Value of a result, or its error printed before exiting, for commands that can't go on
+/// without it.
+pub fn or_exit<T>(result: Result<T, String>) -> T {
+    result.unwrap_or_else(|e| {
+        println!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+/// Reads the API token the web app embeds in marketplace pages, as the "authorization" value of
+/// the page data script.
+fn parse_authorization(page: &scraper::Html) -> String {
+    let script = page.root_element().get_inner_text("script#dsdata");
+    let token = script.find("\"authorization\"").and_then(|start| {
+        let value = script[start + "\"authorization\"".len()..]
+            .trim_start()
+            .strip_prefix(':')?;
+        serde_json::Deserializer::from_str(value)
+            .into_iter::<String>()
+            .next()?
+            .ok()
+    });
+    token.unwrap_or_else(|| {
+        println!("Unable to find the API token, check that the cookies are still valid");
+        std::process::exit(1);
+    })
+}
+
 #[derive(Debug)]
 pub struct DiscogsScraper {
     web: Client,
     api: Client,
+    db: Option<Database>,
+    converter: Option<Converter>,
+    /// Whether pages fetched within the cache TTL are read from the database.
+    page_cache: bool,
+    token: OnceCell<String>,
+    username: OnceCell<String>,
+    /// Wantlist entries by release id, read from the API the first time they are needed.
+    wants: RefCell<Option<Rc<HashMap<u64, Want>>>>,
+    /// Warnings kept for `take_warnings` instead of printed, once `collect_warnings` was called.
+    warnings: RefCell<Option<Vec<String>>>,
 }
 
 impl DiscogsScraper {
@@ -45,10 +135,212 @@ impl DiscogsScraper {
             .user_agent(API_USER_AGENT)
             .build()
             .unwrap();
-        let scraper = DiscogsScraper {
+        DiscogsScraper {
             web: Client::new(web_client, WEB_HOME_URL),
             api: Client::new(api_client, API_HOME_URL),
-        };
-        scraper
+            db: None,
+            converter: None,
+            page_cache: true,
+            token: OnceCell::new(),
+            username: OnceCell::new(),
+            wants: RefCell::new(None),
+            warnings: RefCell::new(None),
+        }
+    }
+
+    pub fn with_database(mut self, db: Database) -> DiscogsScraper {
+        self.db = Some(db);
+        self
+    }
+
+    /// Always scrape pages, still recording them in the database.
+    pub fn without_page_cache(mut self) -> DiscogsScraper {
+        self.page_cache = false;
+        self
+    }
+
+    pub fn database(&self) -> Option<&Database> {
+        self.db.as_ref()
+    }
+
+    pub fn with_converter(mut self, converter: Converter) -> DiscogsScraper {
+        self.converter = Some(converter);
+        self
+    }
+
+    pub fn converter(&self) -> Option<&Converter> {
+        self.converter.as_ref()
+    }
+
+    /// Price in the currency chosen with --currency, when there's one.
+    fn converted(&self, price: &str) -> Option<String> {
+        self.converter
+            .as_ref()
+            .map(|converter| converter.convert_text(price))
+    }
+
+    /// Gets a web page, from the database if it was fetched within the cache TTL. Returns the
+    /// body and the time it was fetched.
+    fn fetch_page(&self, url: &str) -> Result<(String, i64), String> {
+        let cached = self
+            .db
+            .as_ref()
+            .filter(|_| self.page_cache)
+            .and_then(|db| db.cached_page(url));
+        match cached {
+            Some(cached) => Ok(cached),
+            None => self.scrape_page(url),
+        }
+    }
+
+    /// Gets a web page from Discogs, recording it in the database.
+    fn scrape_page(&self, url: &str) -> Result<(String, i64), String> {
+        let mut res = self
+            .web
+            .get(url)
+            .send()
+            .expect("Failed to process request.");
+        // The web client does not follow redirects, but pages like "release/{id}" redirect to
+        // their canonical URL.
+        let mut hops = 0;
+        while res.status().is_redirection() {
+            hops += 1;
+            if hops > MAX_REDIRECTS {
+                return Err(format!("Too many redirects fetching {}", url));
+            }
+            let Some(location) = res.headers().get(LOCATION).and_then(|l| l.to_str().ok()) else {
+                return Err(format!("Redirect without a location fetching {}", url));
+            };
+            let location = location
+                .trim_start_matches(WEB_HOME_URL)
+                .trim_start_matches('/')
+                .to_string();
+            res = self
+                .web
+                .get(&location)
+                .send()
+                .expect("Failed to process request.");
+        }
+        let body = res.text().unwrap();
+        let fetched_at = db::now();
+        if let Some(db) = &self.db {
+            db.store_page(url, &body, fetched_at);
+        }
+        Ok((body, fetched_at))
+    }
+
+    /// Keeps warnings for `take_warnings` instead of printing them, for screens that printing
+    /// would garble.
+    pub fn collect_warnings(&self) {
+        self.warnings.replace(Some(Vec::new()));
+    }
+
+    /// Warnings collected since the last call.
+    pub fn take_warnings(&self) -> Vec<String> {
+        self.warnings
+            .borrow_mut()
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    fn warn(&self, message: &str) {
+        match self.warnings.borrow_mut().as_mut() {
+            Some(warnings) => warnings.push(message.to_string()),
+            None => println!("{}", message),
+        }
+    }
+
+    /// Posts a form of a web page as a browser would. Returns whether Discogs accepted it.
+    fn post_form(&self, action: &str, fields: &[(String, String)]) -> bool {
+        let action = action
+            .trim_start_matches(WEB_HOME_URL)
+            .trim_start_matches('/');
+        let res = self
+            .web
+            .post(action)
+            .form(fields)
+            .send()
+            .expect("Failed to process request.");
+        res.status().is_success() || res.status().is_redirection()
+    }
+
+    /// Drops the wantlist entries read so far and the cached wantlist pages and sellers' wantlist
+    /// pages, once the wantlist changed or has to be read again.
+    pub fn forget_wantlist(&self) {
+        self.wants.replace(None);
+        if let Some(db) = &self.db {
+            db.forget_pages("mywantlist");
+            db.forget_pages("/seller/");
+        }
+    }
+
+    /// Reads the API token up front, for screens where failing to read it later can't exit.
+    pub fn connect(&self) {
+        self.authorization();
+    }
+
+    fn authorization(&self) -> &str {
+        self.token.get_or_init(|| {
+            // A cached page may hold a token that expired since.
+            let (body, _) = or_exit(self.scrape_page(MARKETPLACE_PAGE));
+            parse_authorization(&scraper::Html::parse_document(&body))
+        })
+    }
+
+    /// Items of the pages of an API resource, reading at most `max_pages` pages. `items` takes
+    /// the pagination and the items out of a page.
+    fn api_pages<P: DeserializeOwned, T>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+        max_pages: usize,
+        items: impl Fn(P) -> (Pagination, Vec<T>),
+    ) -> Vec<T> {
+        let mut all = Vec::new();
+        let mut page = 1;
+        loop {
+            let results: P = self
+                .api_get(path)
+                .query(query)
+                .query(&[("page", page), ("per_page", PER_PAGE)])
+                .send_request_json();
+            let (pagination, page_items) = items(results);
+            all.extend(page_items);
+            if page >= max_pages || pagination.page >= pagination.pages {
+                return all;
+            }
+            page += 1;
+        }
+    }
+
+    /// Request to the API authenticated as the logged in user.
+    fn api_get(&self, url: &str) -> RequestBuilder {
+        self.api
+            .get(url)
+            .header(AUTHORIZATION, self.authorization())
+            .header(USER_AGENT, WEB_USER_AGENT)
+    }
+
+    fn api_post(&self, url: &str) -> RequestBuilder {
+        self.api
+            .post(url)
+            .header(AUTHORIZATION, self.authorization())
+            .header(USER_AGENT, WEB_USER_AGENT)
+    }
+
+    fn api_delete(&self, url: &str) -> RequestBuilder {
+        self.api
+            .delete(url)
+            .header(AUTHORIZATION, self.authorization())
+            .header(USER_AGENT, WEB_USER_AGENT)
+    }
+
+    /// Name of the logged in user, which API paths of the collection start with.
+    fn username(&self) -> &str {
+        self.username.get_or_init(|| {
+            let identity: Identity = self.api_get("oauth/identity").send_request_json();
+            identity.username
+        })
     }
 }
@@ -0,0 +1,118 @@
+use super::types::*;
+use super::{form_fields, or_exit, DiscogsScraper};
+
+const PURCHASES: &str = "sell/purchases";
+const ORDER_ITEMS: &str = "table.order_list_table tr.order_row";
+const ORDER_ITEM_LINK: &str = "td.order-item-info a[href*='/release/']";
+const ORDER_MESSAGES: &str = "div.order_message";
+
+/// Text of the first element matching `query`, as pages repeat some of them (e.g. the seller).
+fn first_text(node: &scraper::ElementRef, query: &str) -> String {
+    let selector = scraper::Selector::parse(query).unwrap();
+    node.select(&selector)
+        .next()
+        .map_or(String::new(), |element| element.get_text())
+}
+
+impl DiscogsScraper {
+    /// Orders placed by the user, newest first, scraped from the purchases page. Returns the
+    /// order ids and a row for each with date, seller, status, items, total and tracking.
+    pub fn get_purchases(&self) -> (Vec<String>, Vec<Vec<String>>) {
+        let res = self.web.get(PURCHASES);
+        let purchases_page = scraper::Html::parse_document(&res.send_request());
+        let selector = scraper::Selector::parse("table tr").unwrap();
+        let item_selector = scraper::Selector::parse("td.order_items li").unwrap();
+        let mut ids: Vec<String> = Vec::new();
+        let mut table: Vec<Vec<String>> = Vec::new();
+        for node in purchases_page.select(&selector) {
+            let link = node.get_link("a[href*='/sell/order/']");
+            let Some(id) = link.rsplit('/').next().filter(|id| !id.is_empty()) else {
+                continue;
+            };
+            let items = node.select(&item_selector).count();
+            table.push(vec![
+                id.to_string(),
+                node.get_inner_text("td.order_created, td[data-header='Order Date']"),
+                node.get_inner_text("a[href*='/seller/'], a[href*='/user/']"),
+                node.get_inner_text("td.order_status, td[data-header='Status']"),
+                items.to_string(),
+                node.get_inner_text("td.order_total, td[data-header='Total']"),
+                node.get_inner_text("td.order_tracking, td[data-header='Tracking']"),
+            ]);
+            ids.push(id.to_string());
+        }
+        (ids, table)
+    }
+
+    /// An order read from its order page, which unlike the marketplace API also works for the
+    /// buyer. None when the page shows no order, e.g. for an unknown id.
+    pub fn get_order(&self, order_id: &str) -> Option<Order> {
+        let (body, _) = or_exit(self.fetch_page(&format!("sell/order/{}", order_id)));
+        let order_page = scraper::Html::parse_document(&body);
+        let root = order_page.root_element();
+        let selector = scraper::Selector::parse(ORDER_ITEMS).unwrap();
+        let items: Vec<OrderItem> = order_page
+            .select(&selector)
+            .filter_map(|row| {
+                Some(OrderItem {
+                    release_id: release_id_from_link(&row.get_link(ORDER_ITEM_LINK))?,
+                    description: first_text(&row, ORDER_ITEM_LINK),
+                    price: Price::parse(&first_text(&row, "td.price")),
+                })
+            })
+            .collect();
+        let status = first_text(&root, "span.order_status");
+        if items.is_empty() && status.is_empty() {
+            return None;
+        }
+        let selector = scraper::Selector::parse(ORDER_MESSAGES).unwrap();
+        let messages = order_page
+            .select(&selector)
+            .map(|message| OrderMessage {
+                timestamp: first_text(&message, "span.message_date"),
+                from: first_text(&message, "span.linked_username"),
+                message: first_text(&message, "div.message_body"),
+            })
+            .collect();
+        Some(Order {
+            id: order_id.to_string(),
+            status,
+            created: first_text(&root, "span.order_created"),
+            seller: first_text(&root, "div.box-header-row span.linked_username"),
+            items,
+            shipping: Price::parse(&first_text(
+                &root,
+                "div.order_summary tr.order_shipping td.order_summary_value",
+            )),
+            total: Price::parse(&first_text(
+                &root,
+                "div.order_summary tr.order_total td.order_summary_value",
+            )),
+            messages,
+        })
+    }
+
+    /// Writes to the other party of an order through the message form of its order page, as the
+    /// marketplace API only lets the seller do so.
+    pub fn send_order_message(&self, order_id: &str, message: &str) -> bool {
+        let url = format!("sell/order/{}", order_id);
+        // The form holds a token of the current session, so the page is read again.
+        let (body, _) = or_exit(self.scrape_page(&url));
+        let order_page = scraper::Html::parse_document(&body);
+        let selector = scraper::Selector::parse("form").unwrap();
+        let text_selector = scraper::Selector::parse("textarea[name]").unwrap();
+        let Some((form, text)) = order_page.select(&selector).find_map(|form| {
+            let text = form.select(&text_selector).next()?;
+            Some((form, text.value().attr("name").unwrap().to_string()))
+        }) else {
+            return false;
+        };
+        let mut fields = form_fields(&form);
+        fields.push((text, message.to_string()));
+        let sent = self.post_form(form.value().attr("action").unwrap_or(&url), &fields);
+        if let Some(db) = &self.db {
+            db.forget_pages(&url);
+        }
+        sent
+    }
+}
@@ -0,0 +1,161 @@
+use super::types::*;
+use super::{DiscogsScraper, CONCURRENT_MAX_REQUESTS};
+use futures::{future, stream, StreamExt};
+use reqwest::header::{AUTHORIZATION, USER_AGENT};
+use std::collections::HashMap;
+
+const STATS_LABELS: &[&str] = &[
+    "Have:",
+    "Want:",
+    "Avg Rating:",
+    "Ratings:",
+    "Last Sold:",
+    "Low:",
+    "Median:",
+    "High:",
+];
+
+/// Value following `label` in the statistics box, up to the next known label.
+fn stat_value(text: &str, label: &str) -> Option<String> {
+    let start = text.find(label)? + label.len();
+    let rest = &text[start..];
+    let end = STATS_LABELS
+        .iter()
+        .filter_map(|l| rest.find(l))
+        .min()
+        .unwrap_or(rest.len());
+    let value = rest[..end].trim();
+    match value {
+        "" | "--" => None,
+        value => Some(value.to_string()),
+    }
+}
+
+/// Marketplace statistics of a release: current offers and sales history.
+#[derive(Debug, Clone)]
+pub struct PriceStats {
+    pub lowest: Option<Price>,
+    pub for_sale: Option<usize>,
+    pub sold_low: Option<Price>,
+    pub sold_median: Option<Price>,
+    pub sold_high: Option<Price>,
+    pub last_sold: Option<String>,
+    /// Suggested prices by media condition, best condition first.
+    pub suggestions: Vec<(Condition, Price)>,
+}
+
+impl PriceStats {
+    /// A listing is a deal when it costs less than the median sold price.
+    pub fn is_deal(&self, listing: &Listing) -> bool {
+        match (&self.sold_median, listing.comparable_price()) {
+            (Some(median), Some(price)) => {
+                median.currency == price.currency && price.value < median.value
+            }
+            _ => false,
+        }
+    }
+
+    pub fn suggestion(&self, condition: Condition) -> Option<&Price> {
+        self.suggestions
+            .iter()
+            .find(|(c, _)| *c == condition)
+            .map(|(_, p)| p)
+    }
+}
+
+impl DiscogsScraper {
+    /// Full release data: tracklist, labels, identifiers, credits, community and images. The
+    /// request is authenticated, as image URLs are left blank otherwise.
+    pub fn get_release_details(&self, release_id: u64) -> Release {
+        self.api_get(&format!("releases/{}", release_id))
+            .send_request_json()
+    }
+
+    /// Lowest price and number of listings of a release, the price in `currency` when it is a
+    /// currency code.
+    pub(super) fn marketplace_stats(&self, release_id: u64, currency: &str) -> MarketplaceStats {
+        let mut request = self.api_get(&format!("marketplace/stats/{}", release_id));
+        if currency.len() == 3 {
+            request = request.query(&[("curr_abbr", currency)]);
+        }
+        request.send_request_json()
+    }
+
+    /// Marketplace stats of several releases, each in the currency paired with it, fetched
+    /// concurrently. Releases whose stats can't be read, e.g. over the rate limit, are left out.
+    pub(super) fn releases_stats(
+        &self,
+        releases: &[(u64, String)],
+    ) -> HashMap<u64, MarketplaceStats> {
+        if releases.is_empty() {
+            return HashMap::new();
+        }
+        let token = self.authorization();
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let asynch_client = reqwest::Client::new();
+        rt.block_on(
+            stream::iter(releases)
+                .map(|(release_id, currency)| {
+                    let url = format!("{}/marketplace/stats/{}", super::API_HOME_URL, release_id);
+                    let mut req = asynch_client
+                        .get(&url)
+                        .header(AUTHORIZATION, token)
+                        .header(USER_AGENT, super::WEB_USER_AGENT);
+                    if currency.len() == 3 {
+                        req = req.query(&[("curr_abbr", currency)]);
+                    }
+                    async move {
+                        let res = req.send().await.ok()?.error_for_status().ok()?;
+                        let stats: MarketplaceStats = res.json().await.ok()?;
+                        Some((*release_id, stats))
+                    }
+                })
+                .buffer_unordered(CONCURRENT_MAX_REQUESTS)
+                .filter_map(future::ready)
+                .collect(),
+        )
+    }
+
+    /// Lowest prices of several releases, each in the currency paired with it. Releases without
+    /// listings or whose stats can't be read are left out.
+    pub fn lowest_prices(&self, releases: &[(u64, String)]) -> HashMap<u64, Price> {
+        self.releases_stats(releases)
+            .into_iter()
+            .filter_map(|(release_id, stats)| Some((release_id, stats.lowest_price?)))
+            .collect()
+    }
+
+    pub fn get_price_stats(&self, release_id: u64) -> PriceStats {
+        let stats: MarketplaceStats = self
+            .api_get(&format!("marketplace/stats/{}", release_id))
+            .send_request_json();
+        // Price suggestions need seller settings to be filled in, otherwise an error is returned.
+        let suggestions: HashMap<String, Price> = serde_json::from_str(
+            &self
+                .api_get(&format!("marketplace/price_suggestions/{}", release_id))
+                .send_request(),
+        )
+        .unwrap_or_default();
+        let mut suggestions: Vec<(Condition, Price)> = suggestions
+            .into_iter()
+            .filter_map(|(name, price)| Condition::find(&name).map(|c| (c, price)))
+            .collect();
+        suggestions.sort_by_key(|(c, _)| std::cmp::Reverse(*c));
+
+        let (body, _) = super::or_exit(self.fetch_page(&format!("release/{}", release_id)));
+        let release_page = scraper::Html::parse_document(&body);
+        let text = release_page.root_element().get_inner_text("#release-stats");
+        PriceStats {
+            lowest: stats.lowest_price,
+            for_sale: stats.num_for_sale,
+            sold_low: stat_value(&text, "Low:").and_then(|v| Price::parse(&v)),
+            sold_median: stat_value(&text, "Median:").and_then(|v| Price::parse(&v)),
+            sold_high: stat_value(&text, "High:").and_then(|v| Price::parse(&v)),
+            last_sold: stat_value(&text, "Last Sold:"),
+            suggestions,
+        }
+    }
+}
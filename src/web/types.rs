@@ -1,6 +1,9 @@
+use crate::rates::Converter;
 use itertools::Itertools;
 use reqwest::blocking::{Client as ReqwestClient, RequestBuilder};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
 #[derive(Serialize, Deserialize)]
 pub struct Cookie {
@@ -8,26 +11,101 @@ pub struct Cookie {
     value: String,
 }
 
-impl Cookie {
-    pub fn to_string(&self) -> String {
-        format!("{}={}", self.name, self.value)
+impl fmt::Display for Cookie {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.value)
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Artist {
     name: String,
 }
 
-#[derive(Serialize, Deserialize)]
+/// A release as returned by the API. Only the title and artists are always there.
+#[derive(Deserialize)]
 pub struct Release {
     pub title: String,
     artists: Vec<Artist>,
+    #[serde(default)]
+    pub year: u32,
+    #[serde(default)]
+    pub country: Option<String>,
+    #[serde(default)]
+    pub genres: Vec<String>,
+    #[serde(default)]
+    pub styles: Vec<String>,
+    #[serde(default)]
+    formats: Vec<Format>,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    #[serde(default)]
+    pub tracklist: Vec<Track>,
+    #[serde(default)]
+    pub identifiers: Vec<Identifier>,
+    #[serde(default)]
+    pub extraartists: Vec<Credit>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub community: Option<Community>,
+    #[serde(default)]
+    pub images: Vec<Image>,
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct Script {
-    pub authorization: String,
+#[derive(Deserialize)]
+pub struct Label {
+    pub name: String,
+    #[serde(default)]
+    pub catno: String,
+}
+
+#[derive(Deserialize)]
+pub struct Track {
+    pub position: String,
+    pub title: String,
+    #[serde(default)]
+    pub duration: String,
+    #[serde(default)]
+    pub extraartists: Vec<Credit>,
+}
+
+/// Barcode, matrix/runout and other identifiers printed on a release.
+#[derive(Deserialize)]
+pub struct Identifier {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub value: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct Credit {
+    pub name: String,
+    pub role: String,
+    #[serde(default)]
+    pub tracks: String,
+}
+
+#[derive(Deserialize)]
+pub struct CommunityRating {
+    pub average: f32,
+    pub count: usize,
+}
+
+#[derive(Deserialize)]
+pub struct Community {
+    pub have: usize,
+    pub want: usize,
+    pub rating: CommunityRating,
+}
+
+#[derive(Deserialize)]
+pub struct Image {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub uri: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -35,9 +113,18 @@ pub struct Amount {
     pub amount: usize,
 }
 
+/// Artists of a release as the wantlist search expects them, so every table shows them the same.
+fn artists_text(artists: &[Artist]) -> String {
+    artists.iter().map(|a| a.name.to_string()).join(" ")
+}
+
 impl Release {
     pub fn get_artists(&self) -> String {
-        self.artists.iter().map(|a| a.name.to_string()).join(" ")
+        artists_text(&self.artists)
+    }
+
+    pub fn get_formats(&self) -> String {
+        formats_text(&self.formats)
     }
 }
 
@@ -141,6 +228,1060 @@ pub struct RemovedItems {
     pub success: bool,
 }
 
+/// Goldmine grades used by Discogs for media and sleeve, from worst to best.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Condition {
+    NoCover,
+    NotGraded,
+    Generic,
+    Poor,
+    Fair,
+    Good,
+    GoodPlus,
+    VeryGood,
+    VeryGoodPlus,
+    NearMint,
+    Mint,
+}
+
+const CONDITIONS: &[(Condition, &str, &str)] = &[
+    (Condition::Mint, "Mint", "M"),
+    (Condition::NearMint, "Near Mint", "NM"),
+    (Condition::VeryGoodPlus, "Very Good Plus", "VG+"),
+    (Condition::VeryGood, "Very Good", "VG"),
+    (Condition::GoodPlus, "Good Plus", "G+"),
+    (Condition::Good, "Good", "G"),
+    (Condition::Fair, "Fair", "F"),
+    (Condition::Poor, "Poor", "P"),
+    (Condition::Generic, "Generic", "Generic"),
+    (Condition::NotGraded, "Not Graded", "Not Graded"),
+    (Condition::NoCover, "No Cover", "No Cover"),
+];
+
+impl Condition {
+    /// Returns the grade whose name appears first in `text`. Listings put the grade before its
+    /// description, and the description may mention other grades ("worth 50% of Near Mint").
+    pub fn find(text: &str) -> Option<Condition> {
+        CONDITIONS
+            .iter()
+            .filter_map(|(condition, name, _)| text.find(name).map(|i| (i, name.len(), *condition)))
+            .min_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)))
+            .map(|(_, _, condition)| condition)
+    }
+
+    fn abbreviation(&self) -> &'static str {
+        CONDITIONS.iter().find(|(c, _, _)| c == self).unwrap().2
+    }
+
+    /// Name of the grade expected by the API when listing an item, e.g. "Very Good Plus (VG+)".
+    pub fn api_name(&self) -> String {
+        let (_, name, abbreviation) = CONDITIONS.iter().find(|(c, _, _)| c == self).unwrap();
+        match self {
+            Condition::NearMint => String::from("Near Mint (NM or M-)"),
+            Condition::Generic | Condition::NotGraded | Condition::NoCover => name.to_string(),
+            _ => format!("{} ({})", name, abbreviation),
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.abbreviation())
+    }
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("M-") {
+            return Ok(Condition::NearMint);
+        }
+        CONDITIONS
+            .iter()
+            .find(|(_, name, abbreviation)| {
+                s.eq_ignore_ascii_case(name)
+                    || s.eq_ignore_ascii_case(abbreviation)
+                    || s.eq_ignore_ascii_case(&name.replace(' ', ""))
+            })
+            .map(|(condition, _, _)| *condition)
+            .ok_or(format!(
+                "unknown condition '{}', expected one of: {}",
+                s,
+                CONDITIONS.iter().map(|(_, _, a)| a).join(", ")
+            ))
+    }
+}
+
+/// Media and sleeve grades of a single listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Grading {
+    pub media: Option<Condition>,
+    pub sleeve: Option<Condition>,
+}
+
+impl Grading {
+    /// Parses the text of a listing's condition node, e.g.
+    /// "Media: Very Good Plus (VG+) <description> Sleeve: Near Mint (NM or M-)".
+    pub fn parse(text: &str) -> Grading {
+        let (media, sleeve) = match text.rfind("Sleeve") {
+            Some(i) => (&text[..i], Some(&text[i..])),
+            None => (text, None),
+        };
+        Grading {
+            media: Condition::find(media),
+            sleeve: sleeve.and_then(Condition::find),
+        }
+    }
+}
+
+impl fmt::Display for Grading {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.media {
+            Some(media) => write!(f, "Media: {}", media)?,
+            None => write!(f, "Media: ?")?,
+        }
+        match self.sleeve {
+            Some(sleeve) => write!(f, "\nSleeve: {}", sleeve),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Parses the grading back from how it is displayed in tables, e.g. "Media: VG+\nSleeve: NM".
+impl FromStr for Grading {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut grading = Grading {
+            media: None,
+            sleeve: None,
+        };
+        for line in s.lines() {
+            match line.split_once(':') {
+                Some(("Media", grade)) => grading.media = grade.parse().ok(),
+                Some(("Sleeve", grade)) => grading.sleeve = grade.parse().ok(),
+                _ => return Err(format!("not a grading: '{}'", s)),
+            }
+        }
+        Ok(grading)
+    }
+}
+
+/// Minimum grades a listing must meet, and whether listings are ordered by grade.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ConditionFilter {
+    pub min_media: Option<Condition>,
+    pub min_sleeve: Option<Condition>,
+    pub sort: bool,
+}
+
+impl ConditionFilter {
+    pub fn accepts(&self, grading: &Grading) -> bool {
+        let meets = |min: Option<Condition>, grade: Option<Condition>| match (min, grade) {
+            (None, _) => true,
+            (Some(min), Some(grade)) => grade >= min,
+            (Some(_), None) => false,
+        };
+        meets(self.min_media, grading.media) && meets(self.min_sleeve, grading.sleeve)
+    }
+
+    /// Best grades first. The sort is stable, so equally graded rows keep the page order.
+    pub fn sort<T>(&self, rows: &mut [(Grading, T)]) {
+        if self.sort {
+            rows.sort_by_key(|row| std::cmp::Reverse(row.0));
+        }
+    }
+}
+
+/// A price as shown on Discogs, e.g. "€25.00", "CA$12.50" or "€25.00 EUR".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Price {
+    pub value: f32,
+    pub currency: String,
+}
+
+const CURRENCY_SYMBOLS: &[(&str, &str)] = &[
+    ("CA$", "CAD"),
+    ("A$", "AUD"),
+    ("NZ$", "NZD"),
+    ("MX$", "MXN"),
+    ("R$", "BRL"),
+    ("€", "EUR"),
+    ("£", "GBP"),
+    ("¥", "JPY"),
+    ("$", "USD"),
+];
+
+impl Price {
+    pub fn parse(text: &str) -> Option<Price> {
+        let text = text.trim().trim_start_matches(['+', ' ']);
+        let start = text.find(|c: char| c.is_ascii_digit())?;
+        let (symbol, rest) = text.split_at(start);
+        // Converted prices are prefixed, e.g. "about €12.00"
+        let symbol = symbol.split_whitespace().next_back().unwrap_or("");
+        let end = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+            .unwrap_or(rest.len());
+        let value = rest[..end].replace(',', "").parse().ok()?;
+        let code = rest[end..]
+            .split_whitespace()
+            .next()
+            .filter(|code| code.len() == 3 && code.chars().all(|c| c.is_ascii_uppercase()));
+        let currency = match code {
+            Some(code) => code.to_string(),
+            None => CURRENCY_SYMBOLS
+                .iter()
+                .find(|(s, _)| *s == symbol)
+                .map(|(_, code)| code.to_string())
+                .unwrap_or(symbol.to_string()),
+        };
+        Some(Price { value, currency })
+    }
+}
+
+impl fmt::Display for Price {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match CURRENCY_SYMBOLS
+            .iter()
+            .find(|(_, code)| *code == self.currency)
+        {
+            Some((symbol, _)) => write!(f, "{}{:.2} {}", symbol, self.value, self.currency),
+            None => write!(f, "{:.2} {}", self.value, self.currency),
+        }
+    }
+}
+
+/// Extracts the numeric id from links such as "/release/123-Artist-Title" or
+/// "/sell/release/123?ev=wsim".
+pub fn release_id_from_link(link: &str) -> Option<u64> {
+    let start = link.find("release/")? + "release/".len();
+    link[start..]
+        .split(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Extracts the numeric id from links such as "/master/123-Artist-Title".
+pub fn master_id_from_link(link: &str) -> Option<u64> {
+    let start = link.find("master/")? + "master/".len();
+    link[start..]
+        .split(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// A release row of the wantlist page.
+#[derive(Debug, Clone)]
+pub struct WantlistItem {
+    pub release_id: Option<u64>,
+    pub title: String,
+    pub for_sale: String,
+    pub format: String,
+    pub year: String,
+    pub sellers_link: String,
+    /// Notes and rating (0 when unrated) the user gave the wantlist entry.
+    pub notes: String,
+    pub rating: u8,
+    /// Whether the lowest listing is within the maximum price of the notes, when there is one.
+    pub under_max: Option<bool>,
+    /// Whether this is the very release a lookup by release id was made for.
+    pub exact: bool,
+}
+
+/// Maximum price written in wantlist notes as "max:25EUR", "max:€25" or "max:25" (any currency).
+pub fn max_price(notes: &str) -> Option<Price> {
+    let start = notes.to_ascii_lowercase().find("max:")? + "max:".len();
+    let value = notes[start..]
+        .trim_start()
+        .split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+        .next()?;
+    // Currency codes are often typed in lowercase, e.g. "max:25eur".
+    Price::parse(&value.to_ascii_uppercase())
+}
+
+impl WantlistItem {
+    pub fn max_price(&self) -> Option<Price> {
+        max_price(&self.notes)
+    }
+
+    pub fn row(&self) -> Vec<String> {
+        let budget = match self.under_max {
+            Some(true) => "Under",
+            Some(false) => "Over",
+            None => "",
+        };
+        vec![
+            self.for_sale.clone(),
+            self.title.clone(),
+            self.format.clone(),
+            self.year.clone(),
+            self.notes.clone(),
+            self.max_price()
+                .map_or(String::new(), |max| max.to_string().trim().to_string()),
+            budget.to_string(),
+        ]
+    }
+}
+
+/// Notes and rating of a wantlist entry, as returned by the API.
+#[derive(Debug, Deserialize)]
+pub struct Want {
+    pub id: u64,
+    #[serde(default)]
+    pub rating: u8,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub basic_information: Option<BasicInformation>,
+}
+
+/// Which wantlist entries a random pick can return.
+#[derive(Debug, Default)]
+pub struct RandomFilter {
+    pub with_sellers: bool,
+    pub format: Option<String>,
+    pub genre: Option<String>,
+    pub under: Option<Price>,
+}
+
+impl RandomFilter {
+    /// Checks the format and genre (or style), which the wantlist API returns with each entry.
+    pub fn keeps(&self, want: &Want) -> bool {
+        let Some(info) = &want.basic_information else {
+            return self.format.is_none() && self.genre.is_none();
+        };
+        let format = self.format.as_ref().is_none_or(|format| {
+            info.get_formats()
+                .to_lowercase()
+                .contains(&format.to_lowercase())
+        });
+        let genre = self.genre.as_ref().is_none_or(|genre| {
+            info.genres
+                .iter()
+                .chain(info.styles.iter())
+                .any(|g| g.eq_ignore_ascii_case(genre))
+        });
+        format && genre
+    }
+
+    /// Whether the lowest listing has to be looked up.
+    pub fn needs_marketplace(&self) -> bool {
+        self.with_sellers || self.under.is_some()
+    }
+}
+
+#[derive(Deserialize)]
+pub struct WantsPage {
+    pub pagination: Pagination,
+    pub wants: Vec<Want>,
+}
+
+/// A marketplace listing of a wanted release.
+#[derive(Debug, Clone)]
+pub struct Listing {
+    pub id: String,
+    pub seller: String,
+    pub rating: String,
+    pub wanted: Option<usize>,
+    pub shipping_from: String,
+    pub grading: Grading,
+    pub price: String,
+    /// Price in the user's currency, shown when the seller lists in another one.
+    pub converted: Option<Price>,
+    pub cart_link: String,
+}
+
+impl Listing {
+    pub fn url(&self) -> String {
+        format!("{}/sell/item/{}", super::WEB_HOME_URL, self.id)
+    }
+
+    /// Link adding the listing to the cart, as found on the page or built from its id.
+    pub fn add_to_cart_link(&self) -> String {
+        match self.cart_link.is_empty() {
+            true => format!("sell/cart/?add={}", self.id),
+            false => self.cart_link.clone(),
+        }
+    }
+
+    /// The price in the user's currency when Discogs shows one, otherwise the asking price.
+    pub fn comparable_price(&self) -> Option<Price> {
+        self.converted.clone().or(Price::parse(&self.price))
+    }
+}
+
+/// What happened when adding a listing to the cart.
+#[derive(Debug, Clone)]
+pub enum CartOutcome {
+    Added,
+    AlreadyInCart,
+    Sold(String),
+    SessionExpired,
+    Failed(String),
+}
+
+impl CartOutcome {
+    pub fn is_added(&self) -> bool {
+        matches!(self, CartOutcome::Added | CartOutcome::AlreadyInCart)
+    }
+}
+
+impl fmt::Display for CartOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CartOutcome::Added => write!(f, "Added to cart"),
+            CartOutcome::AlreadyInCart => write!(f, "Already in your cart"),
+            CartOutcome::Sold(message) => write!(f, "No longer available: {}", message),
+            CartOutcome::SessionExpired => {
+                write!(f, "Session expired, export your cookies again")
+            }
+            CartOutcome::Failed(message) => write!(f, "Not added: {}", message),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) enum RemoveControl {
+    /// Submit button of the order form, as name and value.
+    Button(String, String),
+    Link(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct CartItem {
+    pub id: String,
+    pub name: String,
+    pub grading: Grading,
+    pub link: String,
+    pub price: String,
+    pub(super) remove: Option<RemoveControl>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ShippingOption {
+    pub description: String,
+    pub(super) value: String,
+    pub amount: f32,
+    pub selected: bool,
+}
+
+/// Items bought from one seller, with the form used to update them.
+#[derive(Debug, Clone)]
+pub struct CartOrder {
+    pub seller: String,
+    pub rating: String,
+    pub items: Vec<CartItem>,
+    pub subtotal: String,
+    /// Subtotal in the user's currency, when Discogs shows one next to the seller's.
+    pub converted_subtotal: Option<Price>,
+    pub shipping: Vec<ShippingOption>,
+    /// VAT and fees listed in the order summary.
+    pub fees: Vec<(String, Price)>,
+    pub(super) shipping_field: Option<String>,
+    pub(super) action: String,
+    pub(super) fields: Vec<(String, String)>,
+}
+
+impl CartOrder {
+    pub fn title(&self) -> String {
+        format!("{} {}", self.seller, self.rating)
+    }
+
+    pub fn table(&self, filter: &ConditionFilter) -> Vec<Vec<String>> {
+        let mut rows: Vec<(Grading, Vec<String>)> = self
+            .items
+            .iter()
+            .map(|item| {
+                (
+                    item.grading,
+                    vec![
+                        format!(
+                            "{}\n{}\n{}{}",
+                            item.name,
+                            item.grading,
+                            super::WEB_HOME_URL,
+                            item.link
+                        ),
+                        item.price.clone(),
+                    ],
+                )
+            })
+            .collect();
+        // Items already in the cart are only sorted: hiding them would make the subtotal lie.
+        filter.sort(&mut rows);
+        let mut table: Vec<Vec<String>> = rows.into_iter().map(|(_, row)| row).collect();
+        table.push(vec![String::from("Subtotal"), self.subtotal.clone()]);
+        let subtotal = Price::parse(&self.subtotal);
+        for option in self.shipping.iter() {
+            let description = match option.selected {
+                true => format!("{} (selected)", option.description),
+                false => option.description.clone(),
+            };
+            let total = match &subtotal {
+                Some(subtotal) => Price {
+                    value: subtotal.value + option.amount,
+                    currency: subtotal.currency.clone(),
+                }
+                .to_string(),
+                None => format!("+{:.2}", option.amount),
+            };
+            table.push(vec![description, total]);
+        }
+        table
+    }
+
+    /// The selected shipping method, or the cheapest one when none is selected yet.
+    pub fn chosen_shipping(&self) -> Option<&ShippingOption> {
+        self.shipping
+            .iter()
+            .find(|option| option.selected)
+            .or_else(|| {
+                self.shipping
+                    .iter()
+                    .min_by(|a, b| a.amount.total_cmp(&b.amount))
+            })
+    }
+
+    /// Subtotal plus chosen shipping and fees, in the seller's currency.
+    pub fn total(&self) -> Option<Price> {
+        let subtotal = Price::parse(&self.subtotal)?;
+        let shipping = self.chosen_shipping().map_or(0.0, |option| option.amount);
+        let fees: f32 = self
+            .fees
+            .iter()
+            .filter(|(_, fee)| fee.currency == subtotal.currency)
+            .map(|(_, fee)| fee.value)
+            .sum();
+        Some(Price {
+            value: subtotal.value + shipping + fees,
+            currency: subtotal.currency,
+        })
+    }
+
+    /// Total in the user's currency, at the rate Discogs used to convert the subtotal. Discogs
+    /// shows no converted subtotal for sellers already pricing in the user's currency, whose total
+    /// is used as it is.
+    pub fn converted_total(&self) -> Option<Price> {
+        let total = self.total()?;
+        let Some(converted) = &self.converted_subtotal else {
+            return Some(total);
+        };
+        let subtotal = Price::parse(&self.subtotal)?;
+        if subtotal.currency == converted.currency {
+            return Some(total);
+        }
+        if subtotal.value == 0.0 {
+            return None;
+        }
+        Some(Price {
+            value: total.value * converted.value / subtotal.value,
+            currency: converted.currency.clone(),
+        })
+    }
+}
+
+/// One row per seller with items, subtotal, chosen shipping, fees and total, followed by the
+/// grand total of each currency and, when sellers use different currencies, the converted total.
+/// Totals are converted with `converter` when given, otherwise at the rates Discogs shows.
+pub fn cart_totals(orders: &[CartOrder], converter: Option<&Converter>) -> Vec<Vec<String>> {
+    let mut table: Vec<Vec<String>> = Vec::new();
+    let mut totals: Vec<(Price, usize)> = Vec::new();
+    for order in orders {
+        let shipping = match order.chosen_shipping() {
+            Some(option) => format!("{:.2}", option.amount),
+            None => String::from("-"),
+        };
+        let fees = order.fees.iter().map(|(_, fee)| fee.to_string()).join("\n");
+        let total = order.total();
+        table.push(vec![
+            order.seller.clone(),
+            order.items.len().to_string(),
+            order.subtotal.clone(),
+            shipping,
+            fees,
+            total.as_ref().map_or(String::from("-"), Price::to_string),
+        ]);
+        if let Some(total) = total {
+            match totals
+                .iter_mut()
+                .find(|(sum, _)| sum.currency == total.currency)
+            {
+                Some((sum, items)) => {
+                    sum.value += total.value;
+                    *items += order.items.len();
+                }
+                None => totals.push((total, order.items.len())),
+            }
+        }
+    }
+    for (sum, items) in totals.iter() {
+        table.push(vec![
+            String::from("Total"),
+            items.to_string(),
+            String::new(),
+            String::new(),
+            String::new(),
+            sum.to_string(),
+        ]);
+    }
+    if totals.len() > 1
+        || converter.is_some_and(|c| totals.iter().any(|(t, _)| t.currency != c.currency))
+    {
+        let converted: Option<Vec<Price>> = orders
+            .iter()
+            .map(|order| match converter {
+                Some(converter) => converter.convert(&order.total()?),
+                None => order.converted_total(),
+            })
+            .collect();
+        if let Some(converted) = converted.filter(|c| c.iter().map(|p| &p.currency).all_equal()) {
+            table.push(vec![
+                String::from("Total (converted)"),
+                orders
+                    .iter()
+                    .map(|o| o.items.len())
+                    .sum::<usize>()
+                    .to_string(),
+                String::new(),
+                String::new(),
+                String::new(),
+                Price {
+                    value: converted.iter().map(|p| p.value).sum(),
+                    currency: converted[0].currency.clone(),
+                }
+                .to_string(),
+            ]);
+        }
+    }
+    table
+}
+
+#[derive(Deserialize)]
+pub struct Identity {
+    pub username: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Folder {
+    pub id: u64,
+    pub name: String,
+    pub count: usize,
+}
+
+#[derive(Deserialize)]
+pub struct Folders {
+    pub folders: Vec<Folder>,
+}
+
+/// A custom field of the collection, such as "Media Condition" or "Notes".
+#[derive(Debug, Deserialize)]
+pub struct CollectionField {
+    pub id: u64,
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+pub struct CollectionFields {
+    pub fields: Vec<CollectionField>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Format {
+    name: String,
+    #[serde(default)]
+    descriptions: Vec<String>,
+}
+
+/// Formats as Discogs writes them, e.g. "Vinyl, LP, Album; CD".
+fn formats_text(formats: &[Format]) -> String {
+    formats
+        .iter()
+        .map(|f| match f.descriptions.is_empty() {
+            true => f.name.clone(),
+            false => format!("{}, {}", f.name, f.descriptions.join(", ")),
+        })
+        .join("; ")
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BasicInformation {
+    pub title: String,
+    pub year: u32,
+    artists: Vec<Artist>,
+    #[serde(default)]
+    formats: Vec<Format>,
+    #[serde(default)]
+    pub genres: Vec<String>,
+    #[serde(default)]
+    pub styles: Vec<String>,
+}
+
+impl BasicInformation {
+    pub fn get_artists(&self) -> String {
+        artists_text(&self.artists)
+    }
+
+    pub fn get_formats(&self) -> String {
+        formats_text(&self.formats)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Note {
+    pub field_id: u64,
+    pub value: String,
+}
+
+/// One copy of a release in the collection.
+#[derive(Deserialize)]
+pub struct CollectionItem {
+    pub id: u64,
+    pub instance_id: u64,
+    pub folder_id: u64,
+    pub rating: u8,
+    pub basic_information: BasicInformation,
+    #[serde(default)]
+    pub notes: Vec<Note>,
+}
+
+impl CollectionItem {
+    pub fn label(&self) -> String {
+        format!(
+            "{} - {} ({})",
+            self.basic_information.get_artists(),
+            self.basic_information.title,
+            self.id
+        )
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Pagination {
+    pub page: usize,
+    pub pages: usize,
+}
+
+#[derive(Deserialize)]
+pub struct CollectionPage {
+    pub pagination: Pagination,
+    pub releases: Vec<CollectionItem>,
+}
+
+#[derive(Deserialize)]
+pub struct CollectionInstances {
+    pub releases: Vec<CollectionItem>,
+}
+
+#[derive(Deserialize)]
+pub struct AddedInstance {
+    pub instance_id: u64,
+}
+
+pub struct OrderItem {
+    pub release_id: u64,
+    pub description: String,
+    pub price: Option<Price>,
+}
+
+pub struct OrderMessage {
+    pub timestamp: String,
+    pub from: String,
+    pub message: String,
+}
+
+/// An order as its buyer sees it on the order page.
+pub struct Order {
+    pub id: String,
+    pub status: String,
+    pub created: String,
+    pub seller: String,
+    pub items: Vec<OrderItem>,
+    pub shipping: Option<Price>,
+    pub total: Option<Price>,
+    /// Oldest first.
+    pub messages: Vec<OrderMessage>,
+}
+
+#[derive(Deserialize)]
+pub struct InventoryRelease {
+    pub id: u64,
+    pub description: String,
+}
+
+/// A listing of the user's own inventory.
+#[derive(Deserialize)]
+pub struct InventoryListing {
+    pub id: u64,
+    pub status: String,
+    pub condition: String,
+    #[serde(default)]
+    pub sleeve_condition: Option<String>,
+    pub price: Price,
+    #[serde(default)]
+    pub comments: String,
+    pub release: InventoryRelease,
+    #[serde(default)]
+    pub posted: String,
+    #[serde(default)]
+    pub allow_offers: Option<bool>,
+    #[serde(default)]
+    pub location: Option<String>,
+    #[serde(default)]
+    pub external_id: Option<String>,
+    /// Grams, or "auto".
+    #[serde(default)]
+    pub weight: Option<serde_json::Value>,
+    /// Number of items, or "auto".
+    #[serde(default)]
+    pub format_quantity: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+pub struct InventoryPage {
+    pub pagination: Pagination,
+    pub listings: Vec<InventoryListing>,
+}
+
+#[derive(Deserialize)]
+pub struct CreatedListing {
+    pub listing_id: u64,
+}
+
+#[derive(Deserialize)]
+pub struct VersionCommunity {
+    pub in_wantlist: usize,
+    pub in_collection: usize,
+}
+
+#[derive(Deserialize)]
+pub struct VersionStats {
+    pub community: VersionCommunity,
+}
+
+/// A release of a master, as listed by the versions API.
+#[derive(Deserialize)]
+pub struct MasterVersion {
+    pub id: u64,
+    pub title: String,
+    #[serde(default)]
+    pub format: String,
+    #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    pub catno: String,
+    #[serde(default)]
+    pub country: String,
+    #[serde(default)]
+    pub released: String,
+    #[serde(default)]
+    pub stats: Option<VersionStats>,
+}
+
+#[derive(Deserialize)]
+pub struct MasterVersions {
+    pub pagination: Pagination,
+    pub versions: Vec<MasterVersion>,
+}
+
+/// Parameters of a database search: free text plus the facets Discogs filters on.
+#[derive(Debug, Default)]
+pub struct SearchQuery {
+    pub query: Option<String>,
+    pub kind: String,
+    pub genre: Option<String>,
+    pub style: Option<String>,
+    pub format: Option<String>,
+    pub country: Option<String>,
+    pub year: Option<String>,
+    pub label: Option<String>,
+    pub barcode: Option<String>,
+    pub catno: Option<String>,
+}
+
+impl SearchQuery {
+    pub fn params(&self) -> Vec<(&str, &str)> {
+        let mut params = vec![("type", self.kind.as_str())];
+        let facets = [
+            ("q", &self.query),
+            ("genre", &self.genre),
+            ("style", &self.style),
+            ("format", &self.format),
+            ("country", &self.country),
+            ("year", &self.year),
+            ("label", &self.label),
+            ("barcode", &self.barcode),
+            ("catno", &self.catno),
+        ];
+        for (name, value) in facets {
+            if let Some(value) = value {
+                params.push((name, value.as_str()));
+            }
+        }
+        params
+    }
+}
+
+/// Whether the logged in user has a search result, sent with authenticated searches.
+#[derive(Deserialize)]
+pub struct SearchUserData {
+    pub in_wantlist: bool,
+    pub in_collection: bool,
+}
+
+#[derive(Deserialize)]
+pub struct SearchCommunity {
+    pub have: usize,
+    pub want: usize,
+}
+
+/// A release, master, artist or label found by the database search.
+#[derive(Deserialize)]
+pub struct SearchResult {
+    pub id: u64,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub title: String,
+    pub uri: String,
+    #[serde(default)]
+    pub year: Option<String>,
+    #[serde(default)]
+    pub country: Option<String>,
+    #[serde(default)]
+    pub format: Vec<String>,
+    #[serde(default)]
+    pub label: Vec<String>,
+    #[serde(default)]
+    pub catno: Option<String>,
+    #[serde(default)]
+    pub community: Option<SearchCommunity>,
+    #[serde(default)]
+    pub user_data: Option<SearchUserData>,
+}
+
+impl SearchResult {
+    /// Type, title, format, label, catalog number, country, year, have and want.
+    pub fn row(&self) -> Vec<String> {
+        let text = |value: &Option<String>| value.clone().unwrap_or_default();
+        vec![
+            self.kind.clone(),
+            self.title.clone(),
+            self.format.iter().unique().join(", "),
+            self.label.iter().unique().join(", "),
+            text(&self.catno),
+            text(&self.country),
+            text(&self.year),
+            self.community
+                .as_ref()
+                .map_or(String::new(), |c| c.have.to_string()),
+            self.community
+                .as_ref()
+                .map_or(String::new(), |c| c.want.to_string()),
+        ]
+    }
+
+    /// Title, format, label, catalog number, country, year, whether it is in the wantlist and in
+    /// the collection, and the lowest price when it was looked up.
+    pub fn lookup_row(&self, lowest: Option<&Price>) -> Vec<String> {
+        let text = |value: &Option<String>| value.clone().unwrap_or_default();
+        let yes = |owned: bool| match owned {
+            true => String::from("Yes"),
+            false => String::new(),
+        };
+        let (wantlist, collection) = self
+            .user_data
+            .as_ref()
+            .map_or((false, false), |u| (u.in_wantlist, u.in_collection));
+        vec![
+            self.title.clone(),
+            self.format.iter().unique().join(", "),
+            self.label.iter().unique().join(", "),
+            text(&self.catno),
+            text(&self.country),
+            text(&self.year),
+            yes(wantlist),
+            yes(collection),
+            lowest.map_or(String::from("-"), Price::to_string),
+        ]
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SearchPage {
+    pub pagination: Pagination,
+    pub results: Vec<SearchResult>,
+}
+
+/// A master or release of an artist's or label's discography.
+#[derive(Deserialize)]
+pub struct DiscographyItem {
+    pub id: u64,
+    /// "master" or "release"; label discographies only list releases.
+    #[serde(rename = "type", default)]
+    pub kind: Option<String>,
+    pub title: String,
+    #[serde(default)]
+    pub artist: String,
+    #[serde(default)]
+    pub role: Option<String>,
+    #[serde(default)]
+    pub year: Option<u32>,
+    #[serde(default)]
+    pub format: Option<String>,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub catno: Option<String>,
+    #[serde(default)]
+    pub main_release: Option<u64>,
+}
+
+impl DiscographyItem {
+    pub fn is_master(&self) -> bool {
+        self.kind.as_deref() == Some("master")
+    }
+
+    /// The release to add to the wantlist: the main release of a master, or the release itself.
+    pub fn release_id(&self) -> u64 {
+        self.main_release.unwrap_or(self.id)
+    }
+
+    /// Type, year, artist, title, format, label, catalog number and role.
+    pub fn row(&self) -> Vec<String> {
+        let text = |value: &Option<String>| value.clone().unwrap_or_default();
+        vec![
+            match self.is_master() {
+                true => String::from("master"),
+                false => String::from("release"),
+            },
+            self.year
+                .filter(|&year| year > 0)
+                .map_or(String::new(), |year| year.to_string()),
+            self.artist.clone(),
+            self.title.clone(),
+            text(&self.format),
+            text(&self.label),
+            text(&self.catno),
+            text(&self.role),
+        ]
+    }
+}
+
+#[derive(Deserialize)]
+pub struct DiscographyPage {
+    pub pagination: Pagination,
+    pub releases: Vec<DiscographyItem>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct MarketplaceStats {
+    pub lowest_price: Option<Price>,
+    pub num_for_sale: Option<usize>,
+}
+
 #[derive(Debug)]
 pub struct Client {
     client: ReqwestClient,
@@ -162,11 +1303,16 @@ impl Client {
     pub fn get(&self, url: &str) -> RequestBuilder {
         self.client.get(format!("{}/{}", &self.home, url))
     }
+
+    pub fn delete(&self, url: &str) -> RequestBuilder {
+        self.client.delete(format!("{}/{}", &self.home, url))
+    }
 }
 
 pub trait Send {
     fn send_request(self) -> String;
     fn send_request_json<T: DeserializeOwned>(self) -> T;
+    fn send_request_succeeds(self) -> bool;
 }
 
 impl Send for RequestBuilder {
@@ -179,6 +1325,11 @@ impl Send for RequestBuilder {
         let res = self.send().expect("Failed to process request.");
         res.json().unwrap()
     }
+
+    fn send_request_succeeds(self) -> bool {
+        let res = self.send().expect("Failed to process request.");
+        res.status().is_success()
+    }
 }
 
 pub trait ExtendedNode {
@@ -1,66 +1,241 @@
 use super::types::*;
-use super::DiscogsScraper;
-use futures::{stream, StreamExt};
-use itertools::Itertools;
-use reqwest::blocking::multipart;
+use super::{or_exit, DiscogsScraper, PriceStats, CONCURRENT_MAX_REQUESTS};
+use futures::{future, stream, StreamExt};
 use reqwest::header::{AUTHORIZATION, USER_AGENT};
+use reqwest::Url;
 use std::collections::HashMap;
+use std::hash::{BuildHasher, RandomState};
+use std::rc::Rc;
 
-const CONCURRENT_MAX_REQUESTS: usize = 50;
+/// Wantlist item of a wantlist entry read from the API, marked as the exact release looked for.
+/// The sellers link is only set when the marketplace stats show listings.
+fn want_item(want: &Want, stats: &MarketplaceStats) -> WantlistItem {
+    let info = want.basic_information.as_ref();
+    let for_sale = stats.num_for_sale.unwrap_or(0);
+    WantlistItem {
+        release_id: Some(want.id),
+        title: info.map_or(want.id.to_string(), |info| {
+            format!("{} - {}", info.get_artists(), info.title)
+        }),
+        for_sale: match for_sale {
+            0 => String::new(),
+            count => format!("{} for sale", count),
+        },
+        format: info.map_or(String::new(), BasicInformation::get_formats),
+        year: info
+            .filter(|info| info.year > 0)
+            .map_or(String::new(), |info| info.year.to_string()),
+        sellers_link: match for_sale {
+            0 => String::new(),
+            _ => format!("sell/release/{}", want.id),
+        },
+        notes: want.notes.clone(),
+        rating: want.rating,
+        under_max: None,
+        exact: true,
+    }
+}
 
 impl DiscogsScraper {
-    pub fn get_release(&self, query: Option<String>) -> (Vec<String>, Vec<Vec<String>>) {
-        let search = match query {
-            Some(search) => search,
-            None => {
-                let form = multipart::Form::new().text("Action.RandomItem", "Random+Item");
-                let res = self.web.post("mywantlist").multipart(form);
-                let document = scraper::Html::parse_document(&res.send_request());
-                let content = &document.root_element().get_inner_text("p a");
-                let random_release_id = content
-                    .split("/")
-                    .last()
-                    .unwrap()
-                    .split("-")
-                    .next()
-                    .unwrap();
-                let url = format!("releases/{}", random_release_id);
-                let res = self.api.get(&url);
-                let release: Release = res.send_request_json();
-                let artists = release.get_artists();
-                println!("Found: {} - {}", artists, release.title);
-                format!("{} {}", release.title, artists)
-            }
+    /// Wantlist items matching a search or, without one, a random wantlist item.
+    pub fn get_wantlist(&self, query: Option<String>) -> Vec<WantlistItem> {
+        match query {
+            Some(search) => or_exit(self.search_wantlist(&search)),
+            None => self
+                .random_wantlist(&RandomFilter::default())
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// A random wantlist entry passing the filters, as an exact wantlist item. Entries are tried
+    /// in a random order, so the marketplace is only queried until one passes.
+    pub fn random_wantlist(&self, filter: &RandomFilter) -> Option<WantlistItem> {
+        let state = RandomState::new();
+        let wants = self.wants();
+        let mut wants: Vec<&Want> = wants.values().filter(|want| filter.keeps(want)).collect();
+        wants.sort_by_cached_key(|want| state.hash_one(want.id));
+        let currency = filter.under.as_ref().map_or("", |p| p.currency.as_str());
+        wants.into_iter().find_map(|want| {
+            let stats = self.marketplace_stats(want.id, currency);
+            let passes = match (&stats.lowest_price, &filter.under) {
+                _ if !filter.needs_marketplace() => true,
+                (None, _) => false,
+                (Some(lowest), Some(under)) => lowest.value <= under.value,
+                (Some(_), None) => true,
+            };
+            passes.then(|| want_item(want, &stats))
+        })
+    }
+
+    /// The wantlist item of a release, found by id and marked as the exact match, followed by
+    /// the other wantlist items sharing its title and artists.
+    pub fn wantlist_release(&self, release_id: u64) -> Vec<WantlistItem> {
+        let wants = self.wants();
+        let Some(want) = wants.get(&release_id) else {
+            println!("Release {} is not in your wantlist", release_id);
+            return Vec::new();
         };
-        let res = self
-            .web
-            .get(&format!("mywantlist?limit=250&search={}", search));
-        let search_page = scraper::Html::parse_document(&res.send_request());
+        let mut items = vec![want_item(want, &self.marketplace_stats(release_id, ""))];
+        if let Some(info) = &want.basic_information {
+            let similar =
+                or_exit(self.search_wantlist(&format!("{} {}", info.title, info.get_artists())));
+            items.extend(
+                similar
+                    .into_iter()
+                    .filter(|item| item.release_id != Some(release_id)),
+            );
+        }
+        items
+    }
+
+    /// Wantlist items whose page matches a free-text search, without their notes.
+    pub fn search_wantlist(&self, search: &str) -> Result<Vec<WantlistItem>, String> {
+        let url = Url::parse_with_params(
+            &format!("{}/mywantlist", super::WEB_HOME_URL),
+            &[("limit", "250"), ("search", search)],
+        )
+        .unwrap();
+        let (body, fetched_at) =
+            self.fetch_page(&format!("mywantlist?{}", url.query().unwrap_or_default()))?;
+        let search_page = scraper::Html::parse_document(&body);
         let selector = scraper::Selector::parse("tr.shortcut_navigable").unwrap();
-        let releases = search_page.select(&selector);
-        let mut links: Vec<String> = Vec::new();
-        let mut table: Vec<Vec<String>> = Vec::new();
-        for node in releases {
-            let album_info = node.get_inner_text("span.release_title > *:not(:last-child)");
-            let album_sellers = node.get_inner_text("span.marketplace_for_sale_count");
-            let format = node.get_inner_text("td[data-header='Format']");
-            let year = node.get_inner_text("td[data-header='Year']");
-            links.push(node.get_link("span.marketplace_for_sale_count > a"));
-            table.push(vec![album_sellers, album_info, format, year]);
+        let items: Vec<WantlistItem> = search_page
+            .select(&selector)
+            .map(|node| {
+                let release_id =
+                    release_id_from_link(&node.get_link("span.release_title a[href*='/release/']"));
+                WantlistItem {
+                    release_id,
+                    title: node.get_inner_text("span.release_title > *:not(:last-child)"),
+                    for_sale: node.get_inner_text("span.marketplace_for_sale_count"),
+                    format: node.get_inner_text("td[data-header='Format']"),
+                    year: node.get_inner_text("td[data-header='Year']"),
+                    sellers_link: node.get_link("span.marketplace_for_sale_count > a"),
+                    notes: String::new(),
+                    rating: 0,
+                    under_max: None,
+                    exact: false,
+                }
+            })
+            .collect();
+        if let Some(db) = &self.db {
+            db.record_wantlist(&items, fetched_at);
         }
-        (links, table)
+        Ok(items)
     }
 
-    pub fn get_sellers(&self, sellers_link: &str) -> Vec<Vec<String>> {
-        let res = self.web.get(sellers_link);
-        let sellers_page = scraper::Html::parse_document(&res.send_request());
-        let script = sellers_page
-            .root_element()
-            .get_inner_text("script#dsdata")
-            .replace("\n", "");
-        let script: Script =
-            serde_json::from_str(&script[41..1702]).expect("Unable to parse Json file.");
-        let token = script.authorization;
+    /// Wantlist items of every wantlist entry whose artists and title contain all the words of
+    /// `query`, with their notes. Only entries with listings get a sellers link, but entries
+    /// whose stats can't be read get one anyway so they aren't skipped.
+    pub fn watched_items(&self, query: Option<&str>) -> Vec<WantlistItem> {
+        let words: Vec<String> = query
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect();
+        let wants = self.wants();
+        let matching: Vec<&Want> = wants
+            .values()
+            .filter(|want| {
+                let title = want_item(want, &MarketplaceStats::default()).title;
+                let title = title.to_lowercase();
+                words.iter().all(|word| title.contains(word))
+            })
+            .collect();
+        let releases: Vec<(u64, String)> = matching
+            .iter()
+            .map(|want| (want.id, String::new()))
+            .collect();
+        let stats = self.releases_stats(&releases);
+        matching
+            .into_iter()
+            .map(|want| match stats.get(&want.id) {
+                Some(stats) => want_item(want, stats),
+                None => WantlistItem {
+                    sellers_link: format!("sell/release/{}", want.id),
+                    ..want_item(want, &MarketplaceStats::default())
+                },
+            })
+            .collect()
+    }
+
+    /// Fills the notes and rating of wantlist items, which the wantlist page doesn't show.
+    pub fn load_notes(&self, items: &mut [WantlistItem]) {
+        let wants = self.wants();
+        for item in items.iter_mut() {
+            if let Some(want) = item.release_id.and_then(|id| wants.get(&id)) {
+                item.notes = want.notes.clone();
+                item.rating = want.rating;
+            }
+        }
+    }
+
+    /// Marks whether the lowest listing of each item for sale is within the maximum price of its
+    /// notes.
+    pub fn check_budget(&self, items: &mut [WantlistItem]) {
+        let releases: Vec<(u64, String)> = items
+            .iter()
+            .filter(|item| !item.sellers_link.is_empty())
+            .filter_map(|item| Some((item.release_id?, item.max_price()?.currency)))
+            .collect();
+        let lowest = self.lowest_prices(&releases);
+        for item in items.iter_mut() {
+            if let (Some(release_id), Some(max)) = (item.release_id, item.max_price()) {
+                item.under_max = lowest
+                    .get(&release_id)
+                    .map(|lowest| lowest.value <= max.value);
+            }
+        }
+    }
+
+    /// Wantlist entries by release id, read once until the wantlist is forgotten.
+    pub fn wants(&self) -> Rc<HashMap<u64, Want>> {
+        if let Some(wants) = self.wants.borrow().as_ref() {
+            return wants.clone();
+        }
+        let wants = Rc::new(self.get_wants());
+        self.wants.replace(Some(wants.clone()));
+        wants
+    }
+
+    /// Notes and rating of every wantlist entry, by release id. The wantlist page doesn't show
+    /// them, so they are read from the API.
+    fn get_wants(&self) -> HashMap<u64, Want> {
+        let path = format!("users/{}/wants", self.username());
+        self.api_pages(&path, &[], usize::MAX, |page: WantsPage| {
+            (page.pagination, page.wants)
+        })
+        .into_iter()
+        .map(|want| (want.id, want))
+        .collect()
+    }
+
+    /// Changes the notes and/or rating of a wantlist entry.
+    pub fn edit_want(&self, release_id: u64, notes: Option<&str>, rating: Option<u8>) -> bool {
+        let mut params: Vec<(&str, String)> = Vec::new();
+        if let Some(notes) = notes {
+            params.push(("notes", notes.to_string()));
+        }
+        if let Some(rating) = rating {
+            params.push(("rating", rating.to_string()));
+        }
+        let edited = self
+            .api_post(&format!("users/{}/wants/{}", self.username(), release_id))
+            .query(&params)
+            .send_request_succeeds();
+        self.forget_wantlist();
+        edited
+    }
+
+    pub fn get_listings(
+        &self,
+        sellers_link: &str,
+        filter: &ConditionFilter,
+    ) -> Result<Vec<Listing>, String> {
+        let (body, fetched_at) = self.fetch_page(sellers_link)?;
+        let sellers_page = scraper::Html::parse_document(&body);
+        let token = self.authorization();
         let rt = tokio::runtime::Builder::new_multi_thread()
             .enable_all()
             .build()
@@ -68,9 +243,9 @@ impl DiscogsScraper {
         let sellers = sellers_page
             .root_element()
             .get_inner_text("td.seller_info div.seller_block a");
-        let sellers_names: Vec<&str> = sellers.split(" ").collect();
+        let sellers_names: Vec<&str> = sellers.split(' ').collect();
         let asynch_client = reqwest::Client::new();
-        let amounts: HashMap<&str, usize> = rt.block_on(
+        let amounts: Vec<(&str, usize, bool)> = rt.block_on(
             stream::iter(&sellers_names)
                 .take(CONCURRENT_MAX_REQUESTS)
                 .map(|seller| {
@@ -82,71 +257,173 @@ impl DiscogsScraper {
                     let client = &asynch_client;
                     let req = client
                         .get(&url)
-                        .header(AUTHORIZATION, &token)
+                        .header(AUTHORIZATION, token)
                         .header(USER_AGENT, super::WEB_USER_AGENT);
                     async move {
-                        let res = req.send().await.unwrap();
-                        if res.headers()["X-Discogs-Ratelimit-Remaining"] == "10" {
-                            println!("WARNING: less than 10 API calls available!");
-                        }
-                        let body = res.text().await.unwrap();
-                        let amount: Amount =
-                            serde_json::from_str(&body).expect("Error parsing json");
-                        (*seller, amount.amount)
+                        let res = req.send().await.ok()?;
+                        let low = res
+                            .headers()
+                            .get("X-Discogs-Ratelimit-Remaining")
+                            .is_some_and(|remaining| remaining == "10");
+                        let amount: Amount = res.json().await.ok()?;
+                        Some((*seller, amount.amount, low))
                     }
                 })
                 .buffer_unordered(CONCURRENT_MAX_REQUESTS)
+                .filter_map(future::ready)
                 .collect(),
         );
+        if amounts.iter().any(|(_, _, low)| *low) {
+            self.warn("WARNING: less than 10 API calls available!");
+        }
+        let amounts: HashMap<&str, usize> = amounts
+            .into_iter()
+            .map(|(seller, amount, _)| (seller, amount))
+            .collect();
 
         let selector = scraper::Selector::parse("tr.shortcut_navigable").unwrap();
-        let mut table: Vec<Vec<String>> = Vec::new();
-        for (i, node) in sellers_page.select(&selector).enumerate() {
-            let shipping_from =
-                node.get_inner_text("td.seller_info ul li:nth-child(3)")[12..].to_string();
-            let price = node.get_inner_text("td.item_price span.price");
-            // remove condition description which is always found in between media and sleeve nodes
-            let condition = node
-                .get_inner_text("p.item_condition > *:not(.condition-label-desktop)")
-                .split("   ")
-                .enumerate()
-                .filter_map(|(i, c)| if i != 1 { Some(c) } else { None })
-                .join("");
-            let seller = sellers_names[i].to_string();
-            let amount = match amounts.get(sellers_names[i]) {
-                Some(amount) => amount.to_string(),
-                None => "".to_string(),
-            };
-            table.push(vec![seller, amount, shipping_from, condition, price]);
+        let listings: Vec<Listing> = sellers_page
+            .select(&selector)
+            .enumerate()
+            .map(|(i, node)| Listing {
+                id: node
+                    .get_link("a.item_description_title")
+                    .split('/')
+                    .next_back()
+                    .unwrap_or("")
+                    .to_string(),
+                seller: sellers_names[i].to_string(),
+                rating: node
+                    .get_inner_text("td.seller_info ul li:nth-child(2)")
+                    .split(',')
+                    .next()
+                    .unwrap_or("")
+                    .trim()
+                    .to_string(),
+                wanted: amounts.get(sellers_names[i]).copied(),
+                shipping_from: node.get_inner_text("td.seller_info ul li:nth-child(3)")[12..]
+                    .to_string(),
+                grading: Grading::parse(&node.get_inner_text("p.item_condition")),
+                price: node.get_inner_text("td.item_price span.price"),
+                converted: Price::parse(&node.get_inner_text("td.item_price span.converted_price")),
+                cart_link: node.get_link("td.item_add_to_cart > a.button"),
+            })
+            .collect();
+        // Every listing is recorded, the condition filter only applies to what is shown.
+        if let Some(db) = &self.db {
+            db.record_listings(release_id_from_link(sellers_link), &listings, fetched_at);
         }
+        let mut rows: Vec<(Grading, Listing)> = listings
+            .into_iter()
+            .filter(|listing| filter.accepts(&listing.grading))
+            .map(|listing| (listing.grading, listing))
+            .collect();
+        filter.sort(&mut rows);
+
+        Ok(rows.into_iter().map(|(_, listing)| listing).collect())
+    }
 
-        table
+    /// Whether a listing costs at most `max`, or None when the prices can't be compared. A maximum
+    /// without currency is in the currency Discogs shows prices in, so it is compared to the
+    /// price Discogs converted for listings in other currencies. Otherwise the price in the
+    /// currency of `max` is used: as listed, as converted by Discogs or with the --currency rates.
+    pub fn within_max(&self, listing: &Listing, max: &Price) -> Option<bool> {
+        let price = Price::parse(&listing.price)?;
+        if max.currency.is_empty() {
+            let price = listing.converted.as_ref().unwrap_or(&price);
+            return Some(price.value <= max.value);
+        }
+        if price.currency == max.currency {
+            return Some(price.value <= max.value);
+        }
+        let converted = listing
+            .converted
+            .clone()
+            .filter(|converted| converted.currency == max.currency)
+            .or_else(|| {
+                self.converter
+                    .as_ref()
+                    .and_then(|c| c.rates.convert(&price, &max.currency))
+            })?;
+        Some(converted.value <= max.value)
     }
 
-    pub fn get_seller_items(&self, seller: &str) -> (Vec<String>, Vec<Vec<String>>) {
+    /// Sellers of a release. Listings above `max` are hidden when `hide_over_max` is set,
+    /// otherwise marked as "Over max". Listings that can't be compared to `max` are kept.
+    pub fn get_sellers(
+        &self,
+        sellers_link: &str,
+        filter: &ConditionFilter,
+        stats: Option<&PriceStats>,
+        max: Option<&Price>,
+        hide_over_max: bool,
+    ) -> Result<Vec<Vec<String>>, String> {
+        Ok(self
+            .get_listings(sellers_link, filter)?
+            .into_iter()
+            .filter_map(|listing| {
+                let within = max
+                    .and_then(|max| self.within_max(&listing, max))
+                    .unwrap_or(true);
+                if !within && hide_over_max {
+                    return None;
+                }
+                let amount = match listing.wanted {
+                    Some(amount) => amount.to_string(),
+                    None => "".to_string(),
+                };
+                let deal = match stats {
+                    _ if !within => "Over max".to_string(),
+                    Some(stats) if stats.is_deal(&listing) => "Deal".to_string(),
+                    _ => "".to_string(),
+                };
+                let converted = self.converted(&listing.price);
+                let mut row = vec![
+                    listing.seller,
+                    listing.rating,
+                    amount,
+                    listing.shipping_from,
+                    listing.grading.to_string(),
+                    listing.price,
+                ];
+                row.extend(converted);
+                row.push(deal);
+                Some(row)
+            })
+            .collect())
+    }
+
+    pub fn get_seller_items(
+        &self,
+        seller: &str,
+        filter: &ConditionFilter,
+    ) -> Result<(Vec<String>, Vec<Vec<String>>), String> {
         let url = format!("/seller/{}/mywants?limit=250&sort=price%2Casc", seller);
-        let res = self.web.get(&url);
-        let items_page = scraper::Html::parse_document(&res.send_request());
+        let (body, _) = self.fetch_page(&url)?;
+        let items_page = scraper::Html::parse_document(&body);
         let selector = scraper::Selector::parse("tr.shortcut_navigable").unwrap();
-        let mut table: Vec<Vec<String>> = Vec::new();
-        let mut links: Vec<String> = Vec::new();
+        let mut rows: Vec<(Grading, (String, Vec<String>))> = Vec::new();
         for node in items_page.select(&selector) {
+            let grading = Grading::parse(&node.get_inner_text("p.item_condition"));
+            if !filter.accepts(&grading) {
+                continue;
+            }
             let release = node.get_inner_text("a.item_description_title");
             let link = node.get_link("a.item_description_title");
-            let condition =
-                node.get_inner_text("p.item_condition > *:not(.condition-label-desktop)");
             let price = node.get_inner_text("td.item_price span.price");
-            links.push(node.get_link("td.item_add_to_cart > a.button"));
-            table.push(vec![
+            let converted = self.converted(&price);
+            let mut row = vec![
                 format!("{}\n{}{}", release, super::WEB_HOME_URL, link),
-                condition,
+                grading.to_string(),
                 price,
-            ]);
+            ];
+            row.extend(converted);
+            rows.push((
+                grading,
+                (node.get_link("td.item_add_to_cart > a.button"), row),
+            ));
         }
-        (links, table)
-    }
-
-    pub fn add_to_cart(&self, link: &str) {
-        self.web.get(link).send_request();
+        filter.sort(&mut rows);
+        Ok(rows.into_iter().map(|(_, row)| row).unzip())
     }
 }